@@ -0,0 +1,5387 @@
+use async_trait::async_trait;
+use chrono::{
+    DateTime, Datelike, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand};
+use futures::{stream, StreamExt};
+use ollama_rs::{
+    generation::{completion::request::GenerationRequest, options::GenerationOptions},
+    Ollama,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngExt, SeedableRng};
+use reqwest::{header::HeaderValue, NoProxy, Proxy, StatusCode, Url};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{
+        AutoArchiveDuration, ChannelId, ChannelType, MessageId, ReactionType, RoleId, UserId,
+        WebhookId,
+    },
+    builder::{
+        Builder, CreateAllowedMentions, CreateAttachment, CreateEmbed, CreateMessage, CreateThread,
+        EditMessage, ExecuteWebhook,
+    },
+    http::{Http, HttpBuilder},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs::{self, OpenOptions},
+    future::Future,
+    io::{self, Write},
+    net::SocketAddr,
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    slice,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    process::{Child, Command as ProcessCommand},
+    signal,
+    sync::oneshot,
+    time::{sleep, timeout, Instant},
+};
+
+use tracing::{debug, error, info, instrument, warn};
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_LATITUDE: f64 = 55.7558;
+const DEFAULT_LONGITUDE: f64 = 37.6173;
+const DEFAULT_WEATHER_TIMEZONE: &str = "auto";
+const DISCORD_MESSAGE_LIMIT: usize = 2000;
+const DISCORD_EMBED_DESCRIPTION_LIMIT: usize = 4096;
+const DEFAULT_DISCORD_RETRIES: u32 = 3;
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+const DISCORD_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+const DEFAULT_REACTION: &str = "☀️";
+const MIN_SNOWFLAKE: u64 = 1 << 22;
+const MORNING_START_HOUR: u32 = 5;
+const MORNING_END_HOUR: u32 = 12;
+const DEFAULT_WEATHER_RETRIES: u32 = 3;
+const DEFAULT_WEATHER_TTL_SECS: u64 = 30 * 60;
+const DEFAULT_UV_THRESHOLD: f32 = 6.0;
+const DEFAULT_WEATHER_PROVIDERS: &str = "open-meteo,wttr";
+const DEFAULT_OLLAMA_BIN: &str = "ollama";
+const DEFAULT_OLLAMA_HOST: &str = "http://127.0.0.1";
+const DEFAULT_OLLAMA_PORT: u16 = 11434;
+const DEFAULT_OLLAMA_MODEL: &str = "llama3";
+const DEFAULT_OLLAMA_READY_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_OLLAMA_MAX_RESTARTS: u32 = 3;
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const OLLAMA_READY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_LLM_RETRIES: u32 = 2;
+const DEFAULT_LLM_TIMEOUT_SECS: u64 = 60;
+const LLM_RETRY_DELAY: Duration = Duration::from_secs(2);
+const DEFAULT_MAX_GREETING_CHARS: usize = 1500;
+const PER_MEMBER_CONCURRENCY: usize = 4;
+const DEFAULT_PROMPT: &str = "Create a kawaii, uwu and cute morning greeting in {language}, including information about the weather for the day for: {members}. Weather: {weather}. Include this clothing advice: {clothing}. The response should be a direct greeting, without any explanations or additional details.";
+const DEFAULT_DATA_PROMPT: &str =
+    "Members: {members}. Weather: {weather}. Clothing: {clothing}. Language: {language}.";
+const DEFAULT_CLOTHING_SUGGESTION: &str = "dress appropriately for the weather";
+const UMBRELLA_PRECIPITATION_PROBABILITY: f32 = 50.0;
+const CLOTHING_BANDS_C: [f32; 5] = [-10.0, 5.0, 12.0, 18.0, 25.0];
+const CLOTHING: [&str; 6] = [
+    "a heavy winter coat, a hat, a scarf and warm gloves",
+    "a warm coat, a hat and gloves",
+    "a jacket over a sweater",
+    "a light jacket or a sweater",
+    "a t-shirt with something light for the evening",
+    "light breathable clothing",
+];
+const CLOTHING_EXTRAS: [&str; 3] = [
+    "an umbrella or a raincoat",
+    "waterproof boots",
+    "sunglasses and sunscreen",
+];
+const CLOTHING_RU: [&str; 6] = [
+    "тёплый пуховик, шапку, шарф и тёплые перчатки",
+    "тёплое пальто, шапку и перчатки",
+    "куртку поверх свитера",
+    "лёгкую куртку или свитер",
+    "футболку и что-нибудь лёгкое на вечер",
+    "лёгкую дышащую одежду",
+];
+const CLOTHING_EXTRAS_RU: [&str; 3] = [
+    "зонт или дождевик",
+    "непромокаемую обувь",
+    "солнцезащитные очки и крем от солнца",
+];
+const HEALTH_READ_TIMEOUT: Duration = Duration::from_secs(5);
+const WEATHER_ICON_FILE: &str = "weather.png";
+const LAST_SENT_FILE: &str = "good_morning_last_sent";
+const WEATHER_CACHE_FILE: &str = "good_morning_weather.json";
+const WEATHER_CACHE_MAX_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum GoodMorningError {
+    #[error("Failed to find {0}: environment variable not found")]
+    MissingEnv(&'static str),
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    Weather(String),
+    #[error("{0}")]
+    Llm(String),
+    #[error("{0}")]
+    Discord(String),
+    #[error(
+        "ollama binary '{}' not found; install Ollama and make sure it is on PATH, \
+         or point GOOD_MORNING_OLLAMA_BIN at it",
+        .0.display()
+    )]
+    OllamaNotFound(PathBuf),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    InvalidHeader(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("Ollama request failed: {0}")]
+    Ollama(#[from] ollama_rs::error::OllamaError),
+    #[error("Send history database error: {0}")]
+    Database(#[from] rusqlite::Error),
+}
+
+#[derive(Deserialize)]
+pub struct WeatherResponse {
+    pub current_weather: CurrentWeather,
+    pub current_weather_units: Option<CurrentWeatherUnits>,
+    pub hourly: Option<HourlyWeather>,
+    pub daily: Option<DailyForecast>,
+}
+
+#[derive(Deserialize)]
+pub struct CurrentWeatherUnits {
+    pub temperature: String,
+    pub windspeed: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CurrentWeather {
+    pub time: Option<String>,
+    pub temperature: f32,
+    pub weathercode: i32,
+    pub windspeed: Option<f32>,
+    pub winddirection: Option<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct HourlyWeather {
+    #[serde(default)]
+    pub time: Vec<String>,
+    #[serde(default)]
+    pub relative_humidity_2m: Vec<Option<f32>>,
+    #[serde(default)]
+    pub apparent_temperature: Vec<Option<f32>>,
+    #[serde(default)]
+    pub wind_gusts_10m: Vec<Option<f32>>,
+    #[serde(default)]
+    pub cloud_cover: Vec<Option<f32>>,
+}
+
+impl HourlyWeather {
+    fn current_hour_index(&self, current_time: &str) -> Option<usize> {
+        let parse = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").ok();
+        let current_time = parse(current_time)?;
+
+        self.time
+            .iter()
+            .enumerate()
+            .filter_map(|(index, time)| Some((index, parse(time)?)))
+            .min_by_key(|(_, time)| (*time - current_time).num_seconds().abs())
+            .map(|(index, _)| index)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DailyForecast {
+    #[serde(default)]
+    pub temperature_2m_max: Vec<Option<f32>>,
+    #[serde(default)]
+    pub temperature_2m_min: Vec<Option<f32>>,
+    #[serde(default)]
+    pub sunrise: Vec<Option<String>>,
+    #[serde(default)]
+    pub sunset: Vec<Option<String>>,
+    #[serde(default)]
+    pub precipitation_probability_max: Vec<Option<f32>>,
+    #[serde(default)]
+    pub uv_index_max: Vec<Option<f32>>,
+}
+
+#[derive(Deserialize)]
+struct AirQualityResponse {
+    current: AirQualityCurrent,
+}
+
+#[derive(Deserialize)]
+struct AirQualityCurrent {
+    european_aqi: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Deserialize)]
+struct GeocodingResult {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+struct WttrResponse {
+    current_condition: Vec<WttrCondition>,
+}
+
+#[derive(Deserialize)]
+struct WttrCondition {
+    #[serde(rename = "temp_C")]
+    temp_c: String,
+    #[serde(rename = "temp_F")]
+    temp_f: String,
+    #[serde(rename = "weatherDesc", default)]
+    weather_desc: Vec<WttrValue>,
+    #[serde(default)]
+    lang_ru: Vec<WttrValue>,
+    #[serde(default)]
+    lang_ja: Vec<WttrValue>,
+    #[serde(rename = "windspeedKmph")]
+    windspeed_kmph: Option<String>,
+    humidity: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WttrValue {
+    value: String,
+}
+
+impl WttrCondition {
+    /// wttr.in keeps `weatherDesc` in English and puts the `lang=` translation
+    /// under `lang_xx`.
+    fn description(&self, language: Language) -> Option<&str> {
+        let translated = match language {
+            Language::Russian => self.lang_ru.first(),
+            Language::Japanese => self.lang_ja.first(),
+            Language::English => None,
+        };
+
+        translated
+            .or(self.weather_desc.first())
+            .map(|description| description.value.trim())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WeatherCache {
+    timestamp: u64,
+    #[serde(default)]
+    key: String,
+    weather: String,
+    #[serde(default)]
+    code: Option<i32>,
+    #[serde(default)]
+    temperature_c: Option<f32>,
+    #[serde(default)]
+    apparent_temperature_c: Option<f32>,
+    #[serde(default)]
+    precipitation_probability: Option<f32>,
+    #[serde(default)]
+    high_uv_index: Option<f32>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Weather {
+    pub summary: String,
+    pub code: Option<i32>,
+    pub temperature_c: Option<f32>,
+    pub apparent_temperature_c: Option<f32>,
+    pub precipitation_probability: Option<f32>,
+    pub high_uv_index: Option<f32>,
+}
+
+impl Weather {
+    fn clothing_temperature_c(&self) -> Option<f32> {
+        self.apparent_temperature_c.or(self.temperature_c)
+    }
+}
+
+enum Delivery {
+    Bot {
+        token: String,
+        channel_ids: Vec<ChannelId>,
+        edit_message_id: Option<u64>,
+        daily_thread: bool,
+    },
+    Dm {
+        token: String,
+    },
+    Webhook(Webhook),
+}
+
+struct Webhook {
+    id: WebhookId,
+    token: String,
+    username: Option<String>,
+    avatar_url: Option<String>,
+}
+
+#[derive(Clone, Default)]
+struct Status {
+    last_send: Option<u64>,
+    last_error: Option<String>,
+}
+
+pub struct Attachment {
+    filename: String,
+    data: Vec<u8>,
+}
+
+pub struct Footer {
+    pub text: String,
+    pub before_mentions: bool,
+}
+
+impl Footer {
+    fn from_env() -> Result<Option<Self>, GoodMorningError> {
+        let Some(text) = env::var("GOOD_MORNING_FOOTER")
+            .ok()
+            .filter(|text| !text.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let before_mentions = match env::var("GOOD_MORNING_FOOTER_POSITION")
+            .map(|position| position.trim().to_lowercase())
+            .as_deref()
+        {
+            Ok("after") | Ok("") | Err(_) => false,
+            Ok("before") => true,
+            Ok(position) => {
+                return Err(GoodMorningError::Config(format!(
+                    "Unsupported GOOD_MORNING_FOOTER_POSITION '{}', expected one of: after, before",
+                    position
+                )))
+            }
+        };
+
+        Ok(Some(Footer {
+            text,
+            before_mentions,
+        }))
+    }
+
+    fn render(&self) -> String {
+        self.text.replace(
+            "{date}",
+            &Local::now().date_naive().format("%Y-%m-%d").to_string(),
+        )
+    }
+}
+
+pub struct Embed {
+    pub description: String,
+    pub weather_title: String,
+    pub weather: String,
+    pub color: u32,
+}
+
+struct SentMessage<'a> {
+    channel: String,
+    weather: &'a str,
+    message: &'a str,
+    members: &'a [Member],
+}
+
+struct Outgoing<'a> {
+    content: &'a str,
+    embed: Option<&'a Embed>,
+    icon: Option<&'a Attachment>,
+    members: &'a [Member],
+    reaction: Option<&'a str>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MentionOrder {
+    Config,
+    Shuffle,
+    Alphabetical,
+}
+
+impl MentionOrder {
+    fn from_env() -> Result<Self, GoodMorningError> {
+        match env::var("GOOD_MORNING_MENTION_ORDER")
+            .map(|order| order.trim().to_lowercase())
+            .as_deref()
+        {
+            Ok("config") | Ok("") | Err(_) => Ok(MentionOrder::Config),
+            Ok("shuffle") => Ok(MentionOrder::Shuffle),
+            Ok("alphabetical") => Ok(MentionOrder::Alphabetical),
+            Ok(order) => Err(GoodMorningError::Config(format!(
+                "Unsupported GOOD_MORNING_MENTION_ORDER '{}', expected one of: config, shuffle, alphabetical",
+                order
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    fn from_env() -> Self {
+        match env::var("GOOD_MORNING_TEMP_UNIT")
+            .map(|unit| unit.trim().to_lowercase())
+            .as_deref()
+        {
+            Ok("fahrenheit") => TemperatureUnit::Fahrenheit,
+            _ => TemperatureUnit::Celsius,
+        }
+    }
+
+    fn query_param(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MembersConfig {
+    #[serde(default)]
+    members: Vec<MemberEntry>,
+}
+
+#[derive(Deserialize)]
+struct MemberEntry {
+    name: String,
+    id: u64,
+    #[serde(default)]
+    kind: MentionKind,
+    tz: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionKind {
+    #[default]
+    User,
+    Role,
+}
+
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    pub id: u64,
+    pub kind: MentionKind,
+    pub tz: Option<Tz>,
+    pub location: Option<(f64, f64)>,
+}
+
+impl Member {
+    fn mention(&self) -> String {
+        match self.kind {
+            MentionKind::User => format!("<@{}>", self.id),
+            MentionKind::Role => format!("<@&{}>", self.id),
+        }
+    }
+
+    fn is_morning(&self, now: DateTime<Utc>) -> bool {
+        self.tz.is_none_or(|tz| {
+            (MORNING_START_HOUR..MORNING_END_HOUR).contains(&now.with_timezone(&tz).hour())
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Language {
+    Russian,
+    English,
+    Japanese,
+}
+
+impl Language {
+    fn from_env(var: &str, default: Self) -> Result<Self, GoodMorningError> {
+        let code = match env::var(var) {
+            Ok(code) => code.trim().to_lowercase(),
+            Err(_) => return Ok(default),
+        };
+
+        match code.as_str() {
+            "ru" => Ok(Language::Russian),
+            "en" => Ok(Language::English),
+            "ja" => Ok(Language::Japanese),
+            _ => Err(GoodMorningError::Config(format!(
+                "Unsupported {} '{}', expected one of: ru, en, ja",
+                var, code
+            ))),
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Language::Russian => "ru",
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Language::Russian => "Russian",
+            Language::English => "English",
+            Language::Japanese => "Japanese",
+        }
+    }
+
+    fn weather_unavailable(self) -> &'static str {
+        match self {
+            Language::Russian => "не удалось получить данные о погоде",
+            Language::English => "weather data is unavailable",
+            Language::Japanese => "天気情報を取得できませんでした",
+        }
+    }
+
+    fn weather_description(self, code: i32) -> &'static str {
+        match self {
+            Language::Russian => map_weather_code_to_russian_description(code),
+            Language::English | Language::Japanese => map_weather_code_to_description(code),
+        }
+    }
+
+    fn feels_like(self, temperature: &str) -> String {
+        match self {
+            Language::Russian => format!("ощущается как {}", temperature),
+            Language::English | Language::Japanese => format!("feels like {}", temperature),
+        }
+    }
+
+    fn high_and_low(self, high: &str, low: &str) -> String {
+        match self {
+            Language::Russian => format!("максимум {}, минимум {}", high, low),
+            Language::English | Language::Japanese => {
+                format!("high of {}, low of {}", high, low)
+            }
+        }
+    }
+
+    fn chance_of_rain(self, probability: f32) -> String {
+        match self {
+            Language::Russian => format!("вероятность дождя {}%", probability),
+            Language::English | Language::Japanese => {
+                format!("{}% chance of rain", probability)
+            }
+        }
+    }
+
+    fn high_uv_index(self, uv_index: f32) -> String {
+        match self {
+            Language::Russian => format!(
+                "высокий УФ-индекс {}, рекомендуется солнцезащитный крем",
+                uv_index
+            ),
+            Language::English | Language::Japanese => {
+                format!("high UV index {}, sunscreen recommended", uv_index)
+            }
+        }
+    }
+
+    fn air_quality(self, aqi: f32) -> String {
+        match self {
+            Language::Russian => format!(
+                "качество воздуха {} ({})",
+                aqi.round(),
+                describe_european_aqi_in_russian(aqi)
+            ),
+            Language::English | Language::Japanese => format!(
+                "air quality {} ({})",
+                aqi.round(),
+                describe_european_aqi(aqi)
+            ),
+        }
+    }
+
+    fn wind(self, speed: &str, direction: Option<f32>, gusts: Option<&str>) -> String {
+        let mut wind = match self {
+            Language::Russian => format!("ветер {}", speed),
+            Language::English | Language::Japanese => format!("wind {}", speed),
+        };
+
+        if let Some(direction) = direction {
+            match self {
+                Language::Russian => wind.push_str(&format!(
+                    ", направление {}",
+                    compass_direction_in_russian(direction)
+                )),
+                Language::English | Language::Japanese => {
+                    wind.push_str(&format!(" from {}", compass_direction(direction)))
+                }
+            }
+        }
+
+        if let Some(gusts) = gusts {
+            match self {
+                Language::Russian => wind.push_str(&format!(", порывы {}", gusts)),
+                Language::English | Language::Japanese => {
+                    wind.push_str(&format!(", gusts {}", gusts))
+                }
+            }
+        }
+
+        wind
+    }
+
+    fn humidity(self, humidity: f32) -> String {
+        match self {
+            Language::Russian => format!("влажность {}%", humidity),
+            Language::English | Language::Japanese => format!("humidity {}%", humidity),
+        }
+    }
+
+    fn cloud_cover(self, cloud_cover: f32) -> String {
+        match self {
+            Language::Russian => format!("облачность {}%", cloud_cover),
+            Language::English | Language::Japanese => format!("cloud cover {}%", cloud_cover),
+        }
+    }
+
+    fn sunrise(self, time: &str) -> String {
+        match self {
+            Language::Russian => format!("восход {}", time),
+            Language::English | Language::Japanese => format!("sunrise {}", time),
+        }
+    }
+
+    fn sunset(self, time: &str) -> String {
+        match self {
+            Language::Russian => format!("закат {}", time),
+            Language::English | Language::Japanese => format!("sunset {}", time),
+        }
+    }
+
+    fn weather_title(self) -> &'static str {
+        match self {
+            Language::Russian => "Погода",
+            Language::English => "Weather",
+            Language::Japanese => "天気",
+        }
+    }
+
+    fn fallback_template(self) -> &'static str {
+        match self {
+            Language::Russian => {
+                "Доброе утро, {members}! Погода на сегодня: {weather}. Хорошего вам дня!"
+            }
+            Language::English => {
+                "Good morning, {members}! Today's weather: {weather}. Have a great day!"
+            }
+            Language::Japanese => {
+                "おはようございます、{members}！今日の天気：{weather}。良い一日を！"
+            }
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Sends a weather-aware good morning greeting to Discord"
+)]
+pub struct Cli {
+    /// Discord channel id, or several separated by commas
+    #[arg(long, env = "GOOD_MORNING_CHANNEL_ID")]
+    pub channel: Option<String>,
+
+    /// Ollama model used to generate the greeting
+    #[arg(long, env = "GOOD_MORNING_OLLAMA_MODEL", default_value = DEFAULT_OLLAMA_MODEL)]
+    pub model: String,
+
+    /// City to fetch the weather for instead of the configured coordinates
+    #[arg(long, env = "GOOD_MORNING_CITY")]
+    pub city: Option<String>,
+
+    /// Send a single greeting even when GOOD_MORNING_SCHEDULE is set
+    #[arg(long)]
+    pub once: bool,
+
+    /// Print the greeting instead of sending it to Discord
+    #[arg(long, env = "GOOD_MORNING_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Send even if a greeting already went out today
+    #[arg(long)]
+    pub force: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Fetch and print the weather without generating or sending a greeting
+    Weather {
+        /// Also print the raw open-meteo response as JSON
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Generate and print a greeting without sending it to Discord
+    Generate {
+        /// Weather summary to use instead of fetching it
+        #[arg(long)]
+        weather: Option<String>,
+
+        /// Comma separated member names to greet instead of GOOD_MORNING_MEMBERS
+        #[arg(long)]
+        members: Option<String>,
+    },
+}
+
+enum LlmBackendKind {
+    Ollama,
+    OpenAi(OpenAi),
+    Template,
+}
+
+struct OpenAi {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+#[derive(Clone, Copy)]
+enum WeatherSource {
+    OpenMeteo,
+    Wttr,
+}
+
+pub struct Config {
+    http: reqwest::Client,
+    delivery: Option<Delivery>,
+    members: Vec<Member>,
+    language: Language,
+    weather_language: Language,
+    city: Option<String>,
+    latitude: f64,
+    longitude: f64,
+    weather_timezone: String,
+    temperature_unit: TemperatureUnit,
+    weather_sources: Vec<WeatherSource>,
+    weather_retries: u32,
+    cache_dir: PathBuf,
+    db_path: Option<PathBuf>,
+    send_log: Option<PathBuf>,
+    weather_ttl: Duration,
+    uv_threshold: f32,
+    include_aqi: bool,
+    recent_weather: Mutex<HashMap<String, (Instant, Weather)>>,
+    ollama_bin: PathBuf,
+    ollama_log: Option<PathBuf>,
+    ollama_url: Url,
+    ollama_model: String,
+    ollama_ready_timeout: Duration,
+    ollama_max_restarts: u32,
+    auto_pull: bool,
+    llm_backend: LlmBackendKind,
+    prompt: String,
+    weekend_prompt: Option<String>,
+    system_prompt: Option<String>,
+    generation_options: Option<GenerationOptions>,
+    llm_retries: u32,
+    llm_timeout: Duration,
+    stream: bool,
+    fallback_template: Option<String>,
+    use_embed: bool,
+    per_member: bool,
+    mention_order: MentionOrder,
+    mention_seed: Option<u64>,
+    footer: Option<Footer>,
+    schedule: Option<Vec<NaiveTime>>,
+    jitter: Duration,
+    jitter_seed: Option<u64>,
+    weekdays_only: bool,
+    health_addr: Option<SocketAddr>,
+    dry_run: bool,
+    force: bool,
+    max_greeting_chars: usize,
+    attach_icon: bool,
+    reaction: Option<String>,
+    discord_retries: u32,
+    debug_prompt: bool,
+}
+
+impl Config {
+    pub fn load(cli: &Cli) -> Result<Self, GoodMorningError> {
+        let mut errors = Vec::new();
+
+        let http = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_HTTP_TIMEOUT_SECS", DEFAULT_HTTP_TIMEOUT_SECS)
+                .and_then(|timeout_secs| http_client(Duration::from_secs(timeout_secs))),
+        );
+        let delivery = match cli.command {
+            Some(_) => None,
+            None => check(&mut errors, parse_delivery(cli.channel.as_deref())),
+        };
+        let members = match cli.command {
+            Some(Command::Weather { .. })
+            | Some(Command::Generate {
+                members: Some(_), ..
+            }) => Vec::new(),
+            _ => check(&mut errors, parse_members()).unwrap_or_default(),
+        };
+        let language = check(
+            &mut errors,
+            Language::from_env("GOOD_MORNING_LANGUAGE", Language::Russian),
+        )
+        .unwrap_or(Language::Russian);
+        let weather_language = check(
+            &mut errors,
+            Language::from_env("GOOD_MORNING_WEATHER_LANGUAGE", Language::English),
+        )
+        .unwrap_or(Language::English);
+        let latitude = check(
+            &mut errors,
+            parse_coordinate("GOOD_MORNING_LATITUDE", DEFAULT_LATITUDE, 90.0),
+        )
+        .unwrap_or(DEFAULT_LATITUDE);
+        let longitude = check(
+            &mut errors,
+            parse_coordinate("GOOD_MORNING_LONGITUDE", DEFAULT_LONGITUDE, 180.0),
+        )
+        .unwrap_or(DEFAULT_LONGITUDE);
+        let weather_timezone = check(&mut errors, weather_timezone()).unwrap_or_default();
+        let weather_sources = check(&mut errors, weather_sources()).unwrap_or_default();
+        let max_retries = check(&mut errors, parse_env("GOOD_MORNING_MAX_RETRIES")).flatten();
+        let retries = |errors: &mut Vec<String>, var: &str, default: u32| {
+            check(errors, parse_env(var))
+                .flatten()
+                .or(max_retries)
+                .unwrap_or(default)
+        };
+        let weather_retries = retries(
+            &mut errors,
+            "GOOD_MORNING_WEATHER_RETRIES",
+            DEFAULT_WEATHER_RETRIES,
+        );
+        let weather_ttl = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_WEATHER_TTL_SECS", DEFAULT_WEATHER_TTL_SECS),
+        )
+        .unwrap_or(DEFAULT_WEATHER_TTL_SECS);
+        let uv_threshold = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_UV_THRESHOLD", DEFAULT_UV_THRESHOLD),
+        )
+        .unwrap_or(DEFAULT_UV_THRESHOLD);
+        let include_aqi =
+            check(&mut errors, parse_env_or("GOOD_MORNING_INCLUDE_AQI", false)).unwrap_or_default();
+        let ollama_url = check(&mut errors, ollama_url());
+        let ollama_model = check(&mut errors, ollama_model(&cli.model)).unwrap_or_default();
+        let ollama_ready_timeout = check(
+            &mut errors,
+            parse_env_or(
+                "GOOD_MORNING_OLLAMA_READY_TIMEOUT_SECS",
+                DEFAULT_OLLAMA_READY_TIMEOUT_SECS,
+            ),
+        )
+        .unwrap_or(DEFAULT_OLLAMA_READY_TIMEOUT_SECS);
+        let ollama_max_restarts = check(
+            &mut errors,
+            parse_env_or(
+                "GOOD_MORNING_OLLAMA_MAX_RESTARTS",
+                DEFAULT_OLLAMA_MAX_RESTARTS,
+            ),
+        )
+        .unwrap_or(DEFAULT_OLLAMA_MAX_RESTARTS);
+        let llm_backend = check(&mut errors, llm_backend_kind()).unwrap_or(LlmBackendKind::Ollama);
+        let auto_pull =
+            check(&mut errors, parse_env_or("GOOD_MORNING_AUTO_PULL", true)).unwrap_or(true);
+        let system_prompt = system_prompt();
+        let prompt =
+            check(&mut errors, prompt_template(system_prompt.is_some())).unwrap_or_default();
+        let weekend_prompt = check(&mut errors, weekend_prompt_template()).flatten();
+        let generation_options = check(&mut errors, generation_options()).flatten();
+        let llm_retries = retries(&mut errors, "GOOD_MORNING_LLM_RETRIES", DEFAULT_LLM_RETRIES);
+        let llm_timeout = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_LLM_TIMEOUT_SECS", DEFAULT_LLM_TIMEOUT_SECS),
+        )
+        .unwrap_or(DEFAULT_LLM_TIMEOUT_SECS);
+        let stream =
+            check(&mut errors, parse_env_or("GOOD_MORNING_STREAM", false)).unwrap_or_default();
+        let use_embed =
+            check(&mut errors, parse_env_or("GOOD_MORNING_USE_EMBED", false)).unwrap_or_default();
+        let per_member =
+            check(&mut errors, parse_env_or("GOOD_MORNING_PER_MEMBER", false)).unwrap_or_default();
+        let mention_order =
+            check(&mut errors, MentionOrder::from_env()).unwrap_or(MentionOrder::Config);
+        let mention_seed = check(&mut errors, parse_env("GOOD_MORNING_MENTION_SEED")).flatten();
+        let footer = check(&mut errors, Footer::from_env()).flatten();
+        let schedule = check(&mut errors, parse_schedule()).flatten();
+        let jitter_secs =
+            check(&mut errors, parse_env_or("GOOD_MORNING_JITTER_SECS", 0)).unwrap_or_default();
+        let jitter_seed = check(&mut errors, parse_env("GOOD_MORNING_JITTER_SEED")).flatten();
+        let weekdays_only = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_WEEKDAYS_ONLY", false),
+        )
+        .unwrap_or_default();
+        let health_addr = check(&mut errors, parse_env("GOOD_MORNING_HEALTH_ADDR")).flatten();
+        let max_greeting_chars = check(
+            &mut errors,
+            parse_env_or(
+                "GOOD_MORNING_MAX_GREETING_CHARS",
+                DEFAULT_MAX_GREETING_CHARS,
+            ),
+        )
+        .unwrap_or(DEFAULT_MAX_GREETING_CHARS);
+        let discord_retries = retries(
+            &mut errors,
+            "GOOD_MORNING_DISCORD_RETRIES",
+            DEFAULT_DISCORD_RETRIES,
+        );
+        let debug_prompt = check(
+            &mut errors,
+            parse_env_or("GOOD_MORNING_DEBUG_PROMPT", false),
+        )
+        .unwrap_or_default();
+        let attach_icon =
+            check(&mut errors, parse_env_or("GOOD_MORNING_ATTACH_ICON", false)).unwrap_or_default();
+
+        match (http, ollama_url) {
+            (Some(http), Some(ollama_url)) if errors.is_empty() => Ok(Config {
+                http,
+                delivery,
+                members,
+                language,
+                weather_language,
+                city: cli
+                    .city
+                    .as_deref()
+                    .map(|city| city.trim().to_string())
+                    .filter(|city| !city.is_empty()),
+                latitude,
+                longitude,
+                weather_timezone,
+                temperature_unit: TemperatureUnit::from_env(),
+                weather_sources,
+                weather_retries,
+                cache_dir: env::var_os("GOOD_MORNING_CACHE_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(env::temp_dir),
+                db_path: env::var_os("GOOD_MORNING_DB_PATH")
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from),
+                send_log: env::var_os("GOOD_MORNING_SEND_LOG")
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from),
+                weather_ttl: Duration::from_secs(weather_ttl),
+                uv_threshold,
+                include_aqi,
+                recent_weather: Mutex::default(),
+                ollama_bin: env::var_os("GOOD_MORNING_OLLAMA_BIN")
+                    .filter(|bin| !bin.is_empty())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_OLLAMA_BIN)),
+                ollama_log: env::var_os("GOOD_MORNING_OLLAMA_LOG")
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from),
+                ollama_url,
+                ollama_model,
+                ollama_ready_timeout: Duration::from_secs(ollama_ready_timeout),
+                ollama_max_restarts,
+                auto_pull,
+                llm_backend,
+                prompt,
+                weekend_prompt,
+                system_prompt,
+                generation_options,
+                llm_retries,
+                llm_timeout: Duration::from_secs(llm_timeout),
+                stream,
+                fallback_template: env::var("GOOD_MORNING_FALLBACK_TEMPLATE").ok(),
+                use_embed,
+                per_member,
+                mention_order,
+                mention_seed,
+                footer,
+                schedule,
+                jitter: Duration::from_secs(jitter_secs),
+                jitter_seed,
+                weekdays_only,
+                health_addr,
+                dry_run: cli.dry_run,
+                force: cli.force,
+                max_greeting_chars,
+                attach_icon,
+                reaction: match env::var("GOOD_MORNING_REACTION") {
+                    Ok(reaction) => Some(reaction.trim().to_string()).filter(|r| !r.is_empty()),
+                    Err(_) => Some(DEFAULT_REACTION.to_string()),
+                },
+                discord_retries,
+                debug_prompt,
+            }),
+            _ => Err(GoodMorningError::Config(format!(
+                "Invalid configuration ({})",
+                errors.join("; ")
+            ))),
+        }
+    }
+}
+
+/// Builds the shared client for weather and LLM calls. Discord
+/// requests go through serenity, which always sends its own `DiscordBot`
+/// User-Agent as the Discord API requires, so `GOOD_MORNING_USER_AGENT` does
+/// not reach Discord.
+fn http_client(timeout: Duration) -> Result<reqwest::Client, GoodMorningError> {
+    let user_agent = env::var("GOOD_MORNING_USER_AGENT")
+        .ok()
+        .map(|user_agent| user_agent.trim().to_string())
+        .filter(|user_agent| !user_agent.is_empty())
+        .unwrap_or_else(|| USER_AGENT.to_string());
+    let user_agent = HeaderValue::from_str(&user_agent).map_err(|e| {
+        GoodMorningError::Config(format!(
+            "Invalid GOOD_MORNING_USER_AGENT '{}': {}",
+            user_agent, e
+        ))
+    })?;
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .timeout(timeout);
+
+    if let Some(proxy) = http_proxy()? {
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn http_proxy() -> Result<Option<Proxy>, GoodMorningError> {
+    let Some((var, url)) = ["GOOD_MORNING_PROXY", "HTTPS_PROXY", "https_proxy"]
+        .into_iter()
+        .find_map(|var| {
+            env::var(var)
+                .ok()
+                .map(|url| (var, url.trim().to_string()))
+                .filter(|(_, url)| !url.is_empty())
+        })
+    else {
+        return Ok(None);
+    };
+
+    Url::parse(&url)
+        .ok()
+        .filter(|url| url.has_host())
+        .ok_or_else(|| {
+            GoodMorningError::Config(format!(
+                "Invalid {} '{}', expected a URL like http://proxy.example.com:8080",
+                var, url
+            ))
+        })?;
+
+    let no_proxy = ["NO_PROXY", "no_proxy"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok())
+        .unwrap_or_default();
+    let proxy = Proxy::all(&url)
+        .map_err(|e| GoodMorningError::Config(format!("Invalid {} '{}': {}", var, url, e)))?
+        .no_proxy(NoProxy::from_string(&format!(
+            "localhost,127.0.0.1,::1,{}",
+            no_proxy
+        )));
+
+    Ok(Some(proxy))
+}
+
+fn check<T>(errors: &mut Vec<String>, result: Result<T, GoodMorningError>) -> Option<T> {
+    result.map_err(|e| errors.push(e.to_string())).ok()
+}
+
+pub async fn start(cli: Cli) -> Result<(), GoodMorningError> {
+    let config = Config::load(&cli)?;
+
+    if let Some(Command::Weather { verbose }) = cli.command {
+        return print_weather(&config, verbose).await;
+    }
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    let serve_process = if !matches!(config.llm_backend, LlmBackendKind::Ollama)
+        || !is_local_host(&config.ollama_url)
+    {
+        None
+    } else if ollama_is_running(&config.http, &config.ollama_url).await {
+        info!(
+            "Ollama is already running at {}, not spawning `ollama serve`",
+            config.ollama_url
+        );
+        None
+    } else {
+        Some(spawn_ollama_serve(
+            &config.ollama_bin,
+            config.ollama_log.as_deref(),
+        )?)
+    };
+    let serve_process = Arc::new(Mutex::new(serve_process));
+    let signal_serve_process = serve_process.clone();
+
+    tokio::spawn(async move {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+
+        let _ = shutdown_tx.send(());
+
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+
+        warn!("Second Ctrl+C received, exiting immediately");
+
+        if let Some(child) = signal_serve_process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+        {
+            let _ = child.start_kill();
+        }
+
+        process::exit(130);
+    });
+
+    let work = async {
+        if let Some(Command::Generate { weather, members }) = &cli.command {
+            return print_greeting(&config, weather.as_deref(), members.as_deref()).await;
+        }
+
+        match config.schedule.as_deref() {
+            Some(schedule) if !cli.once => {
+                let status = Arc::new(Mutex::new(Status::default()));
+
+                match config.health_addr {
+                    Some(addr) => {
+                        tokio::select! {
+                            result = run_scheduled(&config, schedule, &status, &serve_process) => result,
+                            result = serve_health(
+                                addr,
+                                status.clone(),
+                                config.http.clone(),
+                                config.ollama_url.clone(),
+                            ) => result,
+                        }
+                    }
+                    None => run_scheduled(&config, schedule, &status, &serve_process).await,
+                }
+            }
+            _ => run(&config).await,
+        }
+    };
+
+    let run_result = tokio::select! {
+        _ = shutdown_rx => {
+            info!("Shutdown signal received, cancelling the current run (press Ctrl+C again to force exit)...");
+            Ok(())
+        },
+        result = work => {
+            info!("Application terminated");
+            result
+        },
+    };
+
+    let serve_process = serve_process
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+
+    if serve_process.is_some() {
+        info!("Terminating `ollama serve`...");
+    }
+
+    if let Some(mut serve_process) = serve_process {
+        if serve_process.id().is_some() {
+            let _ = serve_process.kill().await;
+        }
+    }
+
+    run_result
+}
+
+async fn print_weather(config: &Config, verbose: bool) -> Result<(), GoodMorningError> {
+    if !verbose {
+        let providers = weather_providers(config, None);
+        let provider = providers.first().ok_or_else(|| {
+            GoodMorningError::Weather("No weather providers configured".to_string())
+        })?;
+        let weather = provider.fetch().await.map_err(|e| {
+            GoodMorningError::Weather(format!(
+                "Weather provider '{}' failed: {}",
+                provider.name(),
+                e
+            ))
+        })?;
+
+        println!("{}", weather.summary);
+        return Ok(());
+    }
+
+    let provider = open_meteo(
+        config,
+        config.city.clone(),
+        config.latitude,
+        config.longitude,
+    );
+    let (latitude, longitude, forecast) = provider.forecast().await?;
+    let weather = provider
+        .weather(latitude, longitude, forecast.clone())
+        .await?;
+
+    println!("{}", weather.summary);
+    println!("{}", serde_json::to_string_pretty(&forecast)?);
+
+    Ok(())
+}
+
+async fn print_greeting(
+    config: &Config,
+    weather: Option<&str>,
+    members: Option<&str>,
+) -> Result<(), GoodMorningError> {
+    let members = match members {
+        Some(names) => names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Member {
+                name: name.to_string(),
+                id: 0,
+                kind: MentionKind::User,
+                tz: None,
+                location: None,
+            })
+            .collect(),
+        None => config.members.clone(),
+    };
+    let weather = match weather {
+        Some(summary) => Weather {
+            summary: summary.to_string(),
+            code: None,
+            temperature_c: None,
+            apparent_temperature_c: None,
+            precipitation_probability: None,
+            high_uv_index: None,
+        },
+        None => get_weather(config, None).await?,
+    };
+
+    let greeting = match try_llm_backend(config).await? {
+        Some(backend) => {
+            let greeting = generate_greeting(backend.as_ref(), config, &members, &weather).await?;
+            let greeting = sanitize_greeting(&clean_greeting(&greeting), config.max_greeting_chars);
+
+            if let Some(token) = find_raw_mention(&greeting) {
+                warn!(
+                    "Generated greeting contains the mention {}, a real run would use the fallback",
+                    token
+                );
+            }
+
+            greeting
+        }
+        None => fallback_greeting(config, &members, &weather),
+    };
+    println!("{}", greeting);
+
+    Ok(())
+}
+
+async fn ollama_is_running(client: &reqwest::Client, url: &Url) -> bool {
+    matches!(
+        timeout(OLLAMA_PROBE_TIMEOUT, client.get(url.clone()).send()).await,
+        Ok(Ok(response)) if response.status().is_success()
+    )
+}
+
+fn jitter_offset(rng: &mut impl Rng, window: Duration) -> Duration {
+    if window.is_zero() {
+        return Duration::ZERO;
+    }
+
+    Duration::from_millis(rng.random_range(0..=window.as_millis() as u64))
+}
+
+fn supervise_ollama_serve(
+    config: &Config,
+    serve_process: &Mutex<Option<Child>>,
+    restarts: &mut u32,
+) {
+    let mut serve_process = serve_process.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(child) = serve_process.as_mut() else {
+        return;
+    };
+
+    let exit = match child.try_wait() {
+        Ok(None) => {
+            *restarts = 0;
+            return;
+        }
+        Ok(Some(status)) => status.to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    if *restarts >= config.ollama_max_restarts {
+        error!(
+            "`ollama serve` exited ({}) after {} consecutive restarts, giving up",
+            exit, restarts
+        );
+        *serve_process = None;
+        return;
+    }
+
+    *restarts += 1;
+    warn!(
+        "`ollama serve` exited unexpectedly ({}), restarting ({}/{})...",
+        exit, restarts, config.ollama_max_restarts
+    );
+
+    match spawn_ollama_serve(&config.ollama_bin, config.ollama_log.as_deref()) {
+        Ok(child) => *serve_process = Some(child),
+        Err(e) => error!("Failed to restart `ollama serve`: {}", e),
+    }
+}
+
+fn spawn_ollama_serve(
+    ollama_bin: &Path,
+    ollama_log: Option<&Path>,
+) -> Result<Child, GoodMorningError> {
+    let (stdout, stderr) = match ollama_log {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| {
+                    GoodMorningError::Config(format!(
+                        "Failed to open GOOD_MORNING_OLLAMA_LOG '{}': {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+            (Stdio::from(file.try_clone()?), Stdio::from(file))
+        }
+        None => (Stdio::inherit(), Stdio::inherit()),
+    };
+
+    ProcessCommand::new(ollama_bin)
+        .arg("serve")
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => GoodMorningError::OllamaNotFound(ollama_bin.to_path_buf()),
+            _ => GoodMorningError::Io(e),
+        })
+}
+
+fn parse_schedule() -> Result<Option<Vec<NaiveTime>>, GoodMorningError> {
+    let schedule = match env::var("GOOD_MORNING_SCHEDULE") {
+        Ok(schedule) if !schedule.trim().is_empty() => schedule,
+        _ => return Ok(None),
+    };
+
+    let times = schedule
+        .split(',')
+        .map(str::trim)
+        .filter(|time| !time.is_empty())
+        .map(|time| {
+            NaiveTime::parse_from_str(time, "%H:%M").map_err(|e| {
+                GoodMorningError::Config(format!(
+                    "Invalid GOOD_MORNING_SCHEDULE time '{}', expected HH:MM: {}",
+                    time, e
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some(times))
+}
+
+fn next_scheduled_run(schedule: &[NaiveTime], now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+
+    [today, today.succ_opt()?]
+        .into_iter()
+        .flat_map(|date| {
+            schedule
+                .iter()
+                .filter_map(move |time| Local.from_local_datetime(&date.and_time(*time)).earliest())
+        })
+        .filter(|time| *time > now)
+        .min()
+}
+
+fn is_weekend(date: DateTime<Local>) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+async fn run_scheduled(
+    config: &Config,
+    schedule: &[NaiveTime],
+    status: &Mutex<Status>,
+    serve_process: &Mutex<Option<Child>>,
+) -> Result<(), GoodMorningError> {
+    let mut ollama_restarts = 0;
+    let mut rng = match config.jitter_seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => rand::make_rng(),
+    };
+
+    loop {
+        let now = Local::now();
+        let Some(next_run) = next_scheduled_run(schedule, now) else {
+            return Err(GoodMorningError::Config(
+                "GOOD_MORNING_SCHEDULE has no upcoming run times".to_string(),
+            ));
+        };
+
+        let jitter = jitter_offset(&mut rng, config.jitter);
+
+        info!(
+            "Next greeting scheduled for {}",
+            next_run.format("%Y-%m-%d %H:%M")
+        );
+
+        if !jitter.is_zero() {
+            info!("Adding {}s of jitter", jitter.as_secs());
+        }
+
+        sleep((next_run - now).to_std().unwrap_or_default() + jitter).await;
+
+        if config.weekdays_only && is_weekend(next_run) {
+            info!("Skipping weekend");
+            continue;
+        }
+
+        supervise_ollama_serve(config, serve_process, &mut ollama_restarts);
+
+        let providers = weather_providers(config, None);
+        let backend = llm_backend(config).await;
+        let result = match delivery_sink(config) {
+            Ok(sink) => {
+                run_slot(
+                    config,
+                    Some(next_run.time()),
+                    &providers,
+                    backend.as_deref(),
+                    sink.as_ref(),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        let mut status = status.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match result {
+            Ok(true) => {
+                status.last_send = Some(unix_timestamp());
+                status.last_error = None;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!("Scheduled run failed: {}", e);
+                status.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+async fn serve_health(
+    addr: SocketAddr,
+    status: Arc<Mutex<Status>>,
+    client: reqwest::Client,
+    ollama_url: Url,
+) -> Result<(), GoodMorningError> {
+    let listener = TcpListener::bind(addr).await.map_err(|e| {
+        GoodMorningError::Config(format!(
+            "Failed to bind GOOD_MORNING_HEALTH_ADDR {}: {}",
+            addr, e
+        ))
+    })?;
+    info!("Health endpoint listening on http://{}", addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let status = status.clone();
+        let client = client.clone();
+        let ollama_url = ollama_url.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_health_request(stream, &status, &client, &ollama_url).await {
+                debug!("Health request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_health_request(
+    mut stream: TcpStream,
+    status: &Mutex<Status>,
+    client: &reqwest::Client,
+    ollama_url: &Url,
+) -> io::Result<()> {
+    let mut buffer = [0; 1024];
+    let read = timeout(HEALTH_READ_TIMEOUT, stream.read(&mut buffer))
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let (code, content_type, body) = match path {
+        "/health" => ("200 OK", "text/plain", "ok".to_string()),
+        "/status" => {
+            let ollama_reachable = ollama_is_running(client, ollama_url).await;
+            let status = status
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+
+            let body = serde_json::json!({
+                "last_send": status.last_send,
+                "last_error": status.last_error,
+                "ollama_reachable": ollama_reachable
+            });
+            ("200 OK", "application/json", body.to_string())
+        }
+        _ => ("404 Not Found", "text/plain", "not found".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+pub async fn run(config: &Config) -> Result<(), GoodMorningError> {
+    let providers = weather_providers(config, None);
+    let backend = llm_backend(config).await;
+    let sink = delivery_sink(config)?;
+
+    run_slot(config, None, &providers, backend.as_deref(), sink.as_ref())
+        .await
+        .map(|_| ())
+}
+
+fn delivery(config: &Config) -> Result<&Delivery, GoodMorningError> {
+    config
+        .delivery
+        .as_ref()
+        .ok_or_else(|| GoodMorningError::Config("Discord delivery is not configured".to_string()))
+}
+
+fn delivery_sink(config: &Config) -> Result<Box<dyn MessageSink + '_>, GoodMorningError> {
+    let delivery = delivery(config)?;
+
+    Ok(if config.dry_run {
+        Box::new(DryRunSink { delivery })
+    } else {
+        Box::new(DiscordSink { config, delivery })
+    })
+}
+
+/// Greets the members due at `slot`, returning whether a greeting actually went
+/// out (skips and dry runs return `false`).
+async fn run_slot(
+    config: &Config,
+    slot: Option<NaiveTime>,
+    providers: &[Box<dyn WeatherProvider>],
+    backend: Option<&dyn LlmBackend>,
+    sink: &dyn MessageSink,
+) -> Result<bool, GoodMorningError> {
+    let delivery = delivery(config)?;
+    let now = Utc::now();
+    let members = config
+        .members
+        .iter()
+        .filter(|member| {
+            let is_morning = member.is_morning(now);
+            if !is_morning {
+                info!(
+                    "Skipping {}, it is not morning in their timezone",
+                    member.name
+                );
+            }
+            is_morning
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if members.is_empty() && !config.members.is_empty() {
+        info!("No members to greet right now, skipping");
+        return Ok(false);
+    }
+
+    if !config.force && !config.dry_run && already_sent(config, slot) {
+        match slot {
+            Some(slot) => info!(
+                "The {} greeting was already sent today, skipping (pass --force to send anyway)",
+                slot.format("%H:%M")
+            ),
+            None => {
+                info!("A greeting was already sent today, skipping (pass --force to send anyway)")
+            }
+        }
+        return Ok(false);
+    }
+    let weather = recent_weather(config, providers, None)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to get weather: {}", e);
+            Weather {
+                summary: config.language.weather_unavailable().to_string(),
+                code: None,
+                temperature_c: None,
+                apparent_temperature_c: None,
+                precipitation_probability: None,
+                high_uv_index: None,
+            }
+        });
+    let local_weather = local_weather(config, &members).await;
+    let icon = match weather.code.filter(|_| config.attach_icon) {
+        Some(code) => match fetch_weather_icon(&config.http, code).await {
+            Ok(icon) => Some(icon),
+            Err(e) => {
+                warn!("Failed to fetch weather icon, sending text only: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    if let Delivery::Dm { .. } = delivery {
+        let mut messages = Vec::new();
+        let mut summaries = Vec::new();
+
+        for member in &members {
+            if member.kind == MentionKind::Role {
+                warn!("Skipping role '{}', roles cannot receive DMs", member.name);
+                continue;
+            }
+
+            let weather = local_weather.get(&member.id).unwrap_or(&weather);
+            let greeting =
+                greeting_or_fallback(config, backend, slice::from_ref(member), weather).await;
+            let (content, embed) = build_message(config, &[], &greeting, weather);
+            messages.push((member, content, embed));
+            summaries.push(weather.summary.as_str());
+        }
+
+        let mut failures = Vec::new();
+
+        for (member, content, embed) in &messages {
+            if let Err(e) = sink
+                .send(
+                    slice::from_ref(*member),
+                    content,
+                    embed.as_ref(),
+                    icon.as_ref(),
+                )
+                .await
+            {
+                error!("Failed to send DM to {}: {}", member.name, e);
+                failures.push(format!("{}: {}", member.name, e));
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(GoodMorningError::Discord(format!(
+                "Failed to send DMs to {} of {} members ({})",
+                failures.len(),
+                messages.len(),
+                failures.join("; ")
+            )));
+        }
+
+        if config.dry_run {
+            return Ok(false);
+        }
+
+        let sends = messages
+            .iter()
+            .zip(summaries)
+            .map(|((member, content, _), weather)| SentMessage {
+                channel: format!("a DM to {}", member.name),
+                weather,
+                message: content,
+                members: slice::from_ref(*member),
+            })
+            .collect::<Vec<_>>();
+        record_sent(config, slot, &sends);
+        return Ok(true);
+    }
+
+    let (content, weather) = greet(
+        config,
+        &members,
+        &weather,
+        &local_weather,
+        backend,
+        icon.as_ref(),
+        sink,
+    )
+    .await?;
+
+    if !config.dry_run {
+        record_sent(
+            config,
+            slot,
+            &[SentMessage {
+                channel: delivery_target(delivery),
+                weather: &weather.summary,
+                message: &content,
+                members: &members,
+            }],
+        );
+    }
+
+    Ok(!config.dry_run)
+}
+
+async fn llm_backend(config: &Config) -> Option<Box<dyn LlmBackend + '_>> {
+    match try_llm_backend(config).await {
+        Ok(backend) => backend,
+        Err(e) => {
+            warn!("Ollama is unavailable: {}", e);
+            None
+        }
+    }
+}
+
+async fn try_llm_backend(
+    config: &Config,
+) -> Result<Option<Box<dyn LlmBackend + '_>>, GoodMorningError> {
+    match &config.llm_backend {
+        LlmBackendKind::OpenAi(openai) => Ok(Some(Box::new(OpenAiBackend { openai, config }))),
+        LlmBackendKind::Template => Ok(None),
+        LlmBackendKind::Ollama => {
+            let ollama = Ollama::from_url(config.ollama_url.clone());
+            prepare_ollama(&ollama, config).await?;
+
+            Ok(Some(Box::new(OllamaBackend { ollama, config })))
+        }
+    }
+}
+
+async fn greet(
+    config: &Config,
+    members: &[Member],
+    weather: &Weather,
+    local_weather: &HashMap<u64, Weather>,
+    backend: Option<&dyn LlmBackend>,
+    icon: Option<&Attachment>,
+    sink: &dyn MessageSink,
+) -> Result<(String, Weather), GoodMorningError> {
+    let combined = combine_weather(members, weather, local_weather);
+    let greeting = if config.per_member && members.len() > 1 {
+        personal_greetings(config, backend, members, weather, local_weather)
+            .await
+            .join("\n")
+    } else {
+        greeting_or_fallback(config, backend, members, &combined).await
+    };
+    let (content, embed) = build_message(config, members, &greeting, &combined);
+
+    sink.send(members, &content, embed.as_ref(), icon).await?;
+    Ok((content, combined))
+}
+
+async fn personal_greetings(
+    config: &Config,
+    backend: Option<&dyn LlmBackend>,
+    members: &[Member],
+    weather: &Weather,
+    local_weather: &HashMap<u64, Weather>,
+) -> Vec<String> {
+    let generations = stream::iter(
+        members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| async move {
+                let weather = local_weather.get(&member.id).unwrap_or(weather);
+                let greeting =
+                    greeting_or_fallback(config, backend, slice::from_ref(member), weather).await;
+                (index, greeting)
+            }),
+    );
+
+    let mut greetings = generations
+        .buffer_unordered(PER_MEMBER_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+    greetings.sort_by_key(|(index, _)| *index);
+
+    greetings
+        .into_iter()
+        .map(|(_, greeting)| greeting)
+        .collect()
+}
+
+fn record_sent(config: &Config, slot: Option<NaiveTime>, sends: &[SentMessage]) {
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    let last_sent = match slot {
+        Some(slot) => format!("{} {}", today, slot.format("%H:%M")),
+        None => today,
+    };
+
+    if let Err(e) = fs::create_dir_all(&config.cache_dir)
+        .and_then(|()| fs::write(config.cache_dir.join(LAST_SENT_FILE), last_sent))
+    {
+        warn!("Failed to record the last sent date: {}", e);
+    }
+
+    if let Some(path) = &config.db_path {
+        if let Err(e) = record_history(path, slot, sends) {
+            warn!("Failed to record send history in {}: {}", path.display(), e);
+        }
+    }
+
+    if let Some(path) = &config.send_log {
+        if let Err(e) = append_send_log(path, sends) {
+            warn!("Failed to append to send log {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn append_send_log(path: &Path, sends: &[SentMessage]) -> Result<(), GoodMorningError> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let timestamp = Local::now().to_rfc3339();
+
+    for send in sends {
+        let entry = serde_json::json!({
+            "timestamp": timestamp,
+            "channel": send.channel,
+            "weather": send.weather,
+            "message": send.message,
+            "members": send.members.iter().map(|member| &member.name).collect::<Vec<_>>()
+        });
+        writeln!(file, "{}", entry)?;
+    }
+
+    Ok(())
+}
+
+fn already_sent(config: &Config, slot: Option<NaiveTime>) -> bool {
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+
+    if let Some(path) = &config.db_path {
+        match sent_on(path, &today, slot) {
+            Ok(sent) => return sent,
+            Err(e) => warn!("Failed to read send history from {}: {}", path.display(), e),
+        }
+    }
+
+    fs::read_to_string(config.cache_dir.join(LAST_SENT_FILE)).is_ok_and(|last_sent| {
+        let (date, last_slot) = match last_sent.trim().split_once(' ') {
+            Some((date, last_slot)) => (date, Some(last_slot)),
+            None => (last_sent.trim(), None),
+        };
+
+        date == today
+            && slot.is_none_or(|slot| last_slot == Some(slot.format("%H:%M").to_string().as_str()))
+    })
+}
+
+fn open_history(path: &Path) -> Result<Connection, GoodMorningError> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sends (
+            id INTEGER PRIMARY KEY,
+            sent_at TEXT NOT NULL,
+            sent_date TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            weather TEXT NOT NULL,
+            message TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS sends_sent_date ON sends (sent_date);",
+    )?;
+
+    let has_slot: bool = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('sends') WHERE name = 'slot')",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !has_slot {
+        connection.execute_batch("ALTER TABLE sends ADD COLUMN slot TEXT;")?;
+    }
+
+    Ok(connection)
+}
+
+fn record_history(
+    path: &Path,
+    slot: Option<NaiveTime>,
+    sends: &[SentMessage],
+) -> Result<(), GoodMorningError> {
+    let mut connection = open_history(path)?;
+    let transaction = connection.transaction()?;
+    let now = Local::now();
+    let slot = slot.map(|slot| slot.format("%H:%M").to_string());
+
+    for send in sends {
+        transaction.execute(
+            "INSERT INTO sends (sent_at, sent_date, slot, channel, weather, message) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                now.to_rfc3339(),
+                now.date_naive().format("%Y-%m-%d").to_string(),
+                slot,
+                send.channel,
+                send.weather,
+                send.message
+            ],
+        )?;
+    }
+
+    transaction.commit()?;
+    Ok(())
+}
+
+fn sent_on(path: &Path, date: &str, slot: Option<NaiveTime>) -> Result<bool, GoodMorningError> {
+    let connection = open_history(path)?;
+    let slot = slot.map(|slot| slot.format("%H:%M").to_string());
+
+    Ok(connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sends WHERE sent_date = ?1 AND (?2 IS NULL OR slot = ?2))",
+        params![date, slot],
+        |row| row.get(0),
+    )?)
+}
+
+fn delivery_target(delivery: &Delivery) -> String {
+    let join = |channel_ids: &[ChannelId]| {
+        channel_ids
+            .iter()
+            .map(ChannelId::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match delivery {
+        Delivery::Bot {
+            channel_ids,
+            daily_thread: true,
+            ..
+        } => format!("a daily thread in channel {}", join(channel_ids)),
+        Delivery::Bot { channel_ids, .. } => format!("channel {}", join(channel_ids)),
+        Delivery::Dm { .. } => "direct messages".to_string(),
+        Delivery::Webhook(_) => "the configured webhook".to_string(),
+    }
+}
+
+fn print_dry_run(target: &str, content: &str, embed: Option<&Embed>, icon: Option<&Attachment>) {
+    println!("[dry run] Would send to {}:", target);
+    println!("{}", content);
+
+    if let Some(embed) = embed {
+        println!("[embed] {}", embed.description);
+        println!("[embed] {}: {}", embed.weather_title, embed.weather);
+    }
+
+    if let Some(icon) = icon {
+        println!("[attachment] {} ({} bytes)", icon.filename, icon.data.len());
+    }
+}
+
+async fn local_weather(config: &Config, members: &[Member]) -> HashMap<u64, Weather> {
+    let mut weather = HashMap::new();
+
+    for member in members {
+        let Some(location) = member.location else {
+            continue;
+        };
+
+        match get_weather(config, Some(location)).await {
+            Ok(member_weather) => {
+                weather.insert(member.id, member_weather);
+            }
+            Err(e) => warn!(
+                "Failed to get weather for {}, using the default location: {}",
+                member.name, e
+            ),
+        }
+    }
+
+    weather
+}
+
+fn combine_weather(
+    members: &[Member],
+    default: &Weather,
+    local_weather: &HashMap<u64, Weather>,
+) -> Weather {
+    if local_weather.is_empty() {
+        return default.clone();
+    }
+
+    let summary = members
+        .iter()
+        .map(|member| {
+            let weather = local_weather.get(&member.id).unwrap_or(default);
+            format!("{}: {}", member.name, weather.summary)
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    Weather {
+        summary,
+        code: default.code,
+        temperature_c: default.temperature_c,
+        apparent_temperature_c: default.apparent_temperature_c,
+        precipitation_probability: default.precipitation_probability,
+        high_uv_index: default.high_uv_index,
+    }
+}
+
+async fn prepare_ollama(ollama: &Ollama, config: &Config) -> Result<(), GoodMorningError> {
+    wait_for_ollama(&config.http, ollama, config.ollama_ready_timeout).await?;
+
+    if config.auto_pull {
+        ensure_model(ollama, &config.ollama_model).await?;
+    }
+
+    Ok(())
+}
+
+async fn greeting_or_fallback(
+    config: &Config,
+    backend: Option<&dyn LlmBackend>,
+    members: &[Member],
+    weather: &Weather,
+) -> String {
+    if let Some(backend) = backend {
+        match generate_greeting(backend, config, members, weather).await {
+            Ok(greeting) => {
+                let greeting =
+                    sanitize_greeting(&clean_greeting(&greeting), config.max_greeting_chars);
+
+                match find_raw_mention(&greeting) {
+                    Some(token) => warn!(
+                        "Generated greeting contains the mention {}, using fallback",
+                        token
+                    ),
+                    None => return greeting,
+                }
+            }
+            Err(e) => warn!("Failed to generate greeting, using fallback: {}", e),
+        }
+    }
+
+    fallback_greeting(config, members, weather)
+}
+
+fn find_raw_mention(text: &str) -> Option<&str> {
+    text.match_indices("<@").find_map(|(start, _)| {
+        let rest = &text[start + 2..];
+        let id = rest.strip_prefix(['!', '&']).unwrap_or(rest);
+        let after_id = id.trim_start_matches(|c: char| c.is_ascii_digit());
+
+        if after_id.len() == id.len() || !after_id.starts_with('>') {
+            return None;
+        }
+
+        let end = text.len() - after_id.len() + 1;
+        Some(&text[start..end])
+    })
+}
+
+fn clean_greeting(greeting: &str) -> String {
+    let mut greeting = greeting.trim();
+
+    if let Some(inner) = greeting
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        greeting = match inner.split_once('\n') {
+            Some((language, body)) if !language.contains(' ') => body,
+            _ => inner,
+        }
+        .trim();
+    }
+
+    if let Some((first_line, rest)) = greeting.split_once('\n') {
+        let lower = first_line.trim().to_lowercase();
+        let is_meta = lower.ends_with(':')
+            && lower.chars().count() <= 80
+            && ["here", "sure", "okay", "ok,", "of course", "certainly"]
+                .iter()
+                .any(|prefix| lower.starts_with(prefix));
+
+        if is_meta && !rest.trim().is_empty() {
+            greeting = rest.trim();
+        }
+    }
+
+    for (open, close) in [('"', '"'), ('“', '”'), ('«', '»'), ('`', '`'), ('\'', '\'')] {
+        if let Some(inner) = greeting
+            .strip_prefix(open)
+            .and_then(|rest| rest.strip_suffix(close))
+        {
+            if !inner.contains(open) && !inner.contains(close) {
+                greeting = inner.trim();
+            }
+        }
+    }
+
+    let mut cleaned = String::with_capacity(greeting.len());
+    let mut blank_lines = 0;
+
+    for line in greeting.lines() {
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+
+        if !cleaned.is_empty() {
+            cleaned.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+        }
+
+        cleaned.push_str(line.trim_end());
+        blank_lines = 0;
+    }
+
+    cleaned
+}
+
+fn sanitize_greeting(greeting: &str, max_chars: usize) -> String {
+    let mut greeting = greeting.to_string();
+
+    loop {
+        let stripped = greeting.replace("@everyone", "").replace("@here", "");
+
+        if stripped == greeting {
+            break;
+        }
+
+        greeting = stripped;
+    }
+
+    let greeting = greeting.trim();
+
+    if greeting.chars().count() <= max_chars {
+        return greeting.to_string();
+    }
+
+    let truncated = greeting.chars().take(max_chars).collect::<String>();
+    let sentence_end = truncated
+        .char_indices()
+        .rfind(|(_, c)| matches!(c, '.' | '!' | '?' | '。' | '！' | '？'))
+        .map(|(index, c)| index + c.len_utf8());
+
+    match sentence_end {
+        Some(end) => truncated[..end].to_string(),
+        None => truncated,
+    }
+}
+
+fn build_message(
+    config: &Config,
+    members: &[Member],
+    greeting: &str,
+    weather: &Weather,
+) -> (String, Option<Embed>) {
+    let members = &order_mentions(members, config.mention_order, config.mention_seed);
+    let greeting = match weather.code {
+        Some(code) => format!("{} {}", map_weather_code_to_emoji(code), greeting),
+        None => greeting.to_string(),
+    };
+
+    if !config.use_embed {
+        return (
+            format_message(members, &greeting, config.footer.as_ref()),
+            None,
+        );
+    }
+
+    let embed = Embed {
+        description: greeting
+            .chars()
+            .take(DISCORD_EMBED_DESCRIPTION_LIMIT)
+            .collect(),
+        weather_title: config.language.weather_title().to_string(),
+        weather: weather.summary.clone(),
+        color: map_weather_code_to_color(weather.code),
+    };
+
+    (
+        format_message(members, "", config.footer.as_ref()),
+        Some(embed),
+    )
+}
+
+fn parse_delivery(channel: Option<&str>) -> Result<Delivery, GoodMorningError> {
+    let non_empty = |var: &str| env::var(var).ok().filter(|value| !value.trim().is_empty());
+
+    if let Some(url) = non_empty("GOOD_MORNING_WEBHOOK_URL") {
+        let (id, token) = parse_webhook_url(&url)?;
+
+        return Ok(Delivery::Webhook(Webhook {
+            id,
+            token,
+            username: non_empty("GOOD_MORNING_WEBHOOK_USERNAME"),
+            avatar_url: non_empty("GOOD_MORNING_WEBHOOK_AVATAR_URL"),
+        }));
+    }
+
+    let token = discord_token();
+
+    match env::var("GOOD_MORNING_DELIVERY")
+        .map(|mode| mode.trim().to_lowercase())
+        .as_deref()
+    {
+        Ok("dm") => return Ok(Delivery::Dm { token: token? }),
+        Ok("channel") | Ok("") | Err(_) => {}
+        Ok(mode) => {
+            return Err(GoodMorningError::Config(format!(
+                "Unsupported GOOD_MORNING_DELIVERY '{}', expected one of: channel, dm",
+                mode
+            )))
+        }
+    }
+
+    let channel_ids = channel
+        .ok_or(GoodMorningError::MissingEnv("GOOD_MORNING_CHANNEL_ID"))
+        .and_then(parse_channel_ids);
+
+    let edit_message_id = parse_env("GOOD_MORNING_EDIT_MESSAGE_ID");
+    let daily_thread = parse_env_or("GOOD_MORNING_DAILY_THREAD", false);
+
+    match (token, channel_ids, edit_message_id, daily_thread) {
+        (_, _, Ok(Some(_)), Ok(true)) => Err(GoodMorningError::Config(
+            "GOOD_MORNING_EDIT_MESSAGE_ID cannot be combined with GOOD_MORNING_DAILY_THREAD"
+                .to_string(),
+        )),
+        (_, Ok(channel_ids), Ok(Some(_)), _) if channel_ids.len() > 1 => {
+            Err(GoodMorningError::Config(format!(
+                "GOOD_MORNING_EDIT_MESSAGE_ID needs a single GOOD_MORNING_CHANNEL_ID, got {} channels",
+                channel_ids.len()
+            )))
+        }
+        (Ok(token), Ok(channel_ids), Ok(edit_message_id), Ok(daily_thread)) => Ok(Delivery::Bot {
+            token,
+            channel_ids,
+            edit_message_id,
+            daily_thread,
+        }),
+        (token, channel_ids, edit_message_id, daily_thread) => Err(GoodMorningError::Config(
+            [
+                token.err(),
+                channel_ids.err(),
+                edit_message_id.err(),
+                daily_thread.err(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("; "),
+        )),
+    }
+}
+
+fn discord_token() -> Result<String, GoodMorningError> {
+    let path = match env::var_os("GOOD_MORNING_DISCORD_TOKEN_FILE") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => {
+            let token = env::var("GOOD_MORNING_DISCORD_TOKEN")
+                .map_err(|_| GoodMorningError::MissingEnv("GOOD_MORNING_DISCORD_TOKEN"))?;
+            validate_discord_token(&token, "GOOD_MORNING_DISCORD_TOKEN")?;
+            return Ok(token);
+        }
+    };
+
+    let token = fs::read_to_string(&path).map_err(|e| {
+        GoodMorningError::Config(format!(
+            "Failed to read GOOD_MORNING_DISCORD_TOKEN_FILE '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let token = token.trim();
+
+    if token.is_empty() {
+        return Err(GoodMorningError::Config(format!(
+            "GOOD_MORNING_DISCORD_TOKEN_FILE '{}' is empty",
+            path.display()
+        )));
+    }
+
+    validate_discord_token(token, "GOOD_MORNING_DISCORD_TOKEN_FILE")?;
+
+    Ok(token.to_string())
+}
+
+fn validate_discord_token(token: &str, var: &str) -> Result<(), GoodMorningError> {
+    let token = token.strip_prefix("Bot ").unwrap_or(token);
+
+    if token.trim().is_empty() {
+        return Err(GoodMorningError::Config(format!("{} is empty", var)));
+    }
+
+    let segments = token.split('.').collect::<Vec<_>>();
+    let is_well_formed = segments.len() == 3
+        && segments.iter().all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+
+    if !is_well_formed {
+        return Err(GoodMorningError::Config(format!(
+            "{} does not look like a Discord bot token, expected three dot-separated segments",
+            var
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_webhook_url(url: &str) -> Result<(WebhookId, String), GoodMorningError> {
+    let invalid = || {
+        GoodMorningError::Config(
+            "Invalid GOOD_MORNING_WEBHOOK_URL, expected https://discord.com/api/webhooks/<id>/<token>"
+                .to_string(),
+        )
+    };
+
+    let url = Url::parse(url.trim()).map_err(|_| invalid())?;
+    let mut segments = url
+        .path_segments()
+        .ok_or_else(invalid)?
+        .skip_while(|segment| *segment != "webhooks")
+        .skip(1);
+
+    let id = segments
+        .next()
+        .and_then(|id| id.parse::<NonZeroU64>().ok())
+        .ok_or_else(invalid)?;
+    let token = segments
+        .next()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(invalid)?;
+
+    Ok((WebhookId::from(id), token.to_string()))
+}
+
+fn parse_channel_ids(channels_str: &str) -> Result<Vec<ChannelId>, GoodMorningError> {
+    let channel_ids = channels_str
+        .split(',')
+        .map(str::trim)
+        .filter(|channel_id| !channel_id.is_empty())
+        .map(|channel_id| {
+            channel_id
+                .parse::<NonZeroU64>()
+                .map(ChannelId::from)
+                .map_err(|e| {
+                    GoodMorningError::Config(format!(
+                        "Invalid GOOD_MORNING_CHANNEL_ID '{}': {}",
+                        channel_id, e
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if channel_ids.is_empty() {
+        return Err(GoodMorningError::Config(
+            "GOOD_MORNING_CHANNEL_ID must contain at least one channel id".to_string(),
+        ));
+    }
+
+    Ok(channel_ids)
+}
+
+fn parse_members() -> Result<Vec<Member>, GoodMorningError> {
+    let members =
+        match env::var("GOOD_MORNING_CONFIG").or_else(|_| env::var("GOOD_MORNING_MEMBERS_FILE")) {
+            Ok(path) => load_members_file(&path)?,
+            Err(_) => env::var("GOOD_MORNING_MEMBERS")
+                .map_err(|_| GoodMorningError::MissingEnv("GOOD_MORNING_MEMBERS"))
+                .and_then(|members_str| {
+                    parse_members_str(&members_str, members_delimiter()?)
+                        .map_err(GoodMorningError::Config)
+                })?,
+        };
+
+    let members = dedup_members(members);
+
+    for member in &members {
+        if !is_plausible_snowflake(member.id) {
+            warn!(
+                "Id {} of member '{}' does not look like a Discord snowflake",
+                member.id, member.name
+            );
+        }
+    }
+
+    Ok(members)
+}
+
+fn dedup_members(members: Vec<Member>) -> Vec<Member> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+
+    let members = members
+        .into_iter()
+        .filter(|member| {
+            let is_new = seen.insert(member.id);
+            if !is_new {
+                duplicates.push(format!("{} ({})", member.name, member.id));
+            }
+            is_new
+        })
+        .collect();
+
+    if !duplicates.is_empty() {
+        warn!("Removed duplicate members: {}", duplicates.join(", "));
+    }
+
+    members
+}
+
+fn members_delimiter() -> Result<char, GoodMorningError> {
+    match env::var("GOOD_MORNING_MEMBERS_DELIMITER") {
+        Ok(delimiter) if !delimiter.is_empty() => delimiter
+            .parse::<char>()
+            .ok()
+            .filter(|delimiter| *delimiter != '"')
+            .ok_or_else(|| {
+                GoodMorningError::Config(format!(
+                    "GOOD_MORNING_MEMBERS_DELIMITER must be a single character other than '\"', got '{}'",
+                    delimiter
+                ))
+            }),
+        _ => Ok(','),
+    }
+}
+
+fn parse_members_str(members_str: &str, delimiter: char) -> Result<Vec<Member>, String> {
+    if members_str.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let fields = split_fields(members_str, delimiter)
+        .map_err(|e| format!("Malformed GOOD_MORNING_MEMBERS: {}", e))?;
+    let mut members = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, chunk) in fields.chunks(2).enumerate() {
+        match chunk {
+            [name, id_str] => {
+                let id_str = id_str.trim();
+                let (kind, id_digits) = match id_str.strip_prefix("@&") {
+                    Some(role_id) => (MentionKind::Role, role_id.trim_start()),
+                    None => (MentionKind::User, id_str),
+                };
+
+                match id_digits.parse::<u64>() {
+                    Ok(id) => members.push(Member {
+                        name: name.clone(),
+                        id,
+                        kind,
+                        tz: None,
+                        location: None,
+                    }),
+                    Err(e) => errors.push(format!(
+                        "entry {} ('{}{}{}'): invalid id: {}",
+                        index + 1,
+                        name,
+                        delimiter,
+                        id_str,
+                        e
+                    )),
+                }
+            }
+            [name] => errors.push(format!(
+                "entry {} ('{}'): missing id, odd number of fields",
+                index + 1,
+                name
+            )),
+            _ => unreachable!("chunks(2) yields one or two fields"),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(format!(
+            "Malformed GOOD_MORNING_MEMBERS entries: {}",
+            errors.join("; ")
+        ));
+    }
+
+    Ok(members)
+}
+
+fn split_fields(input: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = input.chars().peekable();
+    let is_padding = |c: &char| c.is_whitespace() && *c != delimiter;
+
+    loop {
+        while chars.next_if(is_padding).is_some() {}
+
+        let mut field = String::new();
+
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => {
+                        return Err(format!(
+                            "field {} has an unterminated quote",
+                            fields.len() + 1
+                        ))
+                    }
+                }
+            }
+
+            while chars.next_if(is_padding).is_some() {}
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != delimiter) {
+                field.push(c);
+            }
+
+            field.truncate(field.trim_end().len());
+        }
+
+        fields.push(field);
+
+        match chars.next() {
+            None => return Ok(fields),
+            Some(c) if c == delimiter => {}
+            Some(c) => {
+                return Err(format!(
+                    "unexpected '{}' after quoted field {}",
+                    c,
+                    fields.len()
+                ))
+            }
+        }
+    }
+}
+
+fn is_plausible_snowflake(id: u64) -> bool {
+    let timestamp_ms = (id >> 22) + DISCORD_EPOCH_MS;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(u64::MAX);
+
+    id >= MIN_SNOWFLAKE && id <= i64::MAX as u64 && timestamp_ms <= now_ms
+}
+
+fn load_members_file(path: &str) -> Result<Vec<Member>, GoodMorningError> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        GoodMorningError::Config(format!("Failed to read members file '{}': {}", path, e))
+    })?;
+    let is_toml = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"));
+
+    let members = if is_toml {
+        toml::from_str::<MembersConfig>(&contents)
+            .map(|config| config.members)
+            .map_err(|e| {
+                GoodMorningError::Config(format!("Failed to parse members file '{}': {}", path, e))
+            })?
+    } else {
+        serde_json::from_str::<Vec<MemberEntry>>(&contents).map_err(|e| {
+            GoodMorningError::Config(format!("Failed to parse members file '{}': {}", path, e))
+        })?
+    };
+
+    members
+        .into_iter()
+        .map(|member| {
+            let tz = member
+                .tz
+                .map(|tz| {
+                    tz.parse::<Tz>().map_err(|e| {
+                        GoodMorningError::Config(format!(
+                            "Invalid timezone '{}' for member '{}': {}",
+                            tz, member.name, e
+                        ))
+                    })
+                })
+                .transpose()?;
+
+            let location = match (member.lat, member.lon) {
+                (Some(lat), Some(lon))
+                    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) =>
+                {
+                    Some((lat, lon))
+                }
+                (None, None) => None,
+                (lat, lon) => {
+                    return Err(GoodMorningError::Config(format!(
+                        "Invalid coordinates for member '{}': lat {:?}, lon {:?}",
+                        member.name, lat, lon
+                    )))
+                }
+            };
+
+            Ok(Member {
+                name: member.name.trim().to_string(),
+                id: member.id,
+                kind: member.kind,
+                tz,
+                location,
+            })
+        })
+        .collect()
+}
+
+fn parse_env<T>(var: &str) -> Result<Option<T>, GoodMorningError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match env::var(var) {
+        Ok(value) => value.trim().parse::<T>().map(Some).map_err(|e| {
+            GoodMorningError::Config(format!("Failed to parse {} '{}': {}", var, value, e))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_env_or<T>(var: &str, default: T) -> Result<T, GoodMorningError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    Ok(parse_env(var)?.unwrap_or(default))
+}
+
+fn parse_coordinate(var: &str, default: f64, max: f64) -> Result<f64, GoodMorningError> {
+    let value = parse_env_or(var, default)?;
+
+    if !(-max..=max).contains(&value) {
+        return Err(GoodMorningError::Config(format!(
+            "{} must be between {} and {}, got {}",
+            var, -max, max, value
+        )));
+    }
+
+    Ok(value)
+}
+
+fn weather_timezone() -> Result<String, GoodMorningError> {
+    let timezone = env::var("GOOD_MORNING_TIMEZONE")
+        .map(|timezone| timezone.trim().to_string())
+        .unwrap_or_default();
+
+    if timezone.is_empty() || timezone.eq_ignore_ascii_case(DEFAULT_WEATHER_TIMEZONE) {
+        return Ok(DEFAULT_WEATHER_TIMEZONE.to_string());
+    }
+
+    timezone.parse::<Tz>().map_err(|e| {
+        GoodMorningError::Config(format!(
+            "Invalid GOOD_MORNING_TIMEZONE '{}': {}",
+            timezone, e
+        ))
+    })?;
+
+    Ok(timezone)
+}
+
+fn is_retryable(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request()
+}
+
+async fn get_with_retries(
+    client: &reqwest::Client,
+    url: &str,
+    retries: u32,
+) -> Result<reqwest::Response, GoodMorningError> {
+    let mut attempt = 0;
+
+    loop {
+        match client.get(url).send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) if !response.status().is_server_error() || attempt >= retries => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+
+                return Err(GoodMorningError::Weather(
+                    format!("Weather request failed: {} {}", status, body.trim())
+                        .trim_end()
+                        .to_string(),
+                ));
+            }
+            Ok(response) => {
+                let delay = Duration::from_secs(1 << attempt.min(6));
+                attempt += 1;
+                warn!(
+                    "Weather request returned {}, retrying in {}s ({}/{})...",
+                    response.status(),
+                    delay.as_secs(),
+                    attempt,
+                    retries
+                );
+                sleep(delay).await;
+            }
+            Err(e) if attempt < retries && is_retryable(&e) => {
+                let delay = Duration::from_secs(1 << attempt.min(6));
+                attempt += 1;
+                warn!(
+                    "Weather request failed: {}, retrying in {}s ({}/{})...",
+                    e,
+                    delay.as_secs(),
+                    attempt,
+                    retries
+                );
+                sleep(delay).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+async fn geocode_city(
+    client: &reqwest::Client,
+    name: &str,
+    retries: u32,
+) -> Result<(f64, f64), GoodMorningError> {
+    let url = Url::parse_with_params(
+        "https://geocoding-api.open-meteo.com/v1/search",
+        &[("name", name), ("count", "1")],
+    )
+    .map_err(|e| GoodMorningError::Weather(format!("Failed to build geocoding URL: {}", e)))?;
+    let response: GeocodingResponse = get_with_retries(client, url.as_str(), retries)
+        .await?
+        .json()
+        .await?;
+
+    response
+        .results
+        .first()
+        .map(|result| (result.latitude, result.longitude))
+        .ok_or_else(|| GoodMorningError::Weather(format!("city '{}' not found", name)))
+}
+
+async fn fetch_air_quality(
+    client: &reqwest::Client,
+    latitude: f64,
+    longitude: f64,
+    retries: u32,
+) -> Result<Option<f32>, GoodMorningError> {
+    let url = Url::parse_with_params(
+        "https://air-quality-api.open-meteo.com/v1/air-quality",
+        &[
+            ("latitude", latitude.to_string().as_str()),
+            ("longitude", longitude.to_string().as_str()),
+            ("current", "european_aqi"),
+        ],
+    )
+    .map_err(|e| GoodMorningError::Weather(format!("Failed to build air quality URL: {}", e)))?;
+    let response: AirQualityResponse = get_with_retries(client, url.as_str(), retries)
+        .await?
+        .json()
+        .await?;
+
+    Ok(response.current.european_aqi)
+}
+
+fn describe_european_aqi(aqi: f32) -> &'static str {
+    match aqi {
+        aqi if aqi < 20.0 => "good",
+        aqi if aqi < 40.0 => "fair",
+        aqi if aqi < 60.0 => "moderate",
+        aqi if aqi < 80.0 => "poor",
+        aqi if aqi < 100.0 => "very poor",
+        _ => "extremely poor",
+    }
+}
+
+fn describe_european_aqi_in_russian(aqi: f32) -> &'static str {
+    match aqi {
+        aqi if aqi < 20.0 => "хорошее",
+        aqi if aqi < 40.0 => "удовлетворительное",
+        aqi if aqi < 60.0 => "умеренное",
+        aqi if aqi < 80.0 => "плохое",
+        aqi if aqi < 100.0 => "очень плохое",
+        _ => "крайне плохое",
+    }
+}
+
+#[async_trait(?Send)]
+trait WeatherProvider {
+    fn name(&self) -> &'static str;
+
+    async fn fetch(&self) -> Result<Weather, GoodMorningError>;
+}
+
+struct OpenMeteo {
+    city: Option<String>,
+    latitude: f64,
+    longitude: f64,
+    timezone: String,
+    unit: TemperatureUnit,
+    language: Language,
+    uv_threshold: f32,
+    include_aqi: bool,
+    retries: u32,
+    client: reqwest::Client,
+}
+
+#[async_trait(?Send)]
+impl WeatherProvider for OpenMeteo {
+    fn name(&self) -> &'static str {
+        "open-meteo"
+    }
+
+    async fn fetch(&self) -> Result<Weather, GoodMorningError> {
+        let (latitude, longitude, forecast) = self.forecast().await?;
+        self.weather(latitude, longitude, forecast).await
+    }
+}
+
+impl OpenMeteo {
+    async fn forecast(&self) -> Result<(f64, f64, serde_json::Value), GoodMorningError> {
+        let (latitude, longitude) = match &self.city {
+            Some(name) => geocode_city(&self.client, name, self.retries).await?,
+            None => (self.latitude, self.longitude),
+        };
+
+        let url = Url::parse_with_params(
+            "https://api.open-meteo.com/v1/forecast",
+            &[
+                ("latitude", latitude.to_string().as_str()),
+                ("longitude", longitude.to_string().as_str()),
+                ("current_weather", "true"),
+                (
+                    "hourly",
+                    "relative_humidity_2m,apparent_temperature,wind_gusts_10m,cloud_cover",
+                ),
+                (
+                    "daily",
+                    "temperature_2m_max,temperature_2m_min,sunrise,sunset,precipitation_probability_max,uv_index_max",
+                ),
+                ("timezone", self.timezone.as_str()),
+                ("forecast_days", "1"),
+                ("temperature_unit", self.unit.query_param()),
+            ],
+        )
+        .map_err(|e| GoodMorningError::Weather(format!("Failed to build forecast URL: {}", e)))?;
+        let forecast = get_with_retries(&self.client, url.as_str(), self.retries)
+            .await?
+            .json()
+            .await?;
+
+        Ok((latitude, longitude, forecast))
+    }
+
+    async fn weather(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        forecast: serde_json::Value,
+    ) -> Result<Weather, GoodMorningError> {
+        let response: WeatherResponse = serde_json::from_value(forecast)?;
+        let current = &response.current_weather;
+        let units = response.current_weather_units.as_ref();
+        let unit_symbol = units
+            .map(|units| units.temperature.as_str())
+            .unwrap_or_else(|| self.unit.symbol());
+
+        let current_hour = response
+            .hourly
+            .as_ref()
+            .zip(current.time.as_deref())
+            .and_then(|(hourly, time)| Some((hourly, hourly.current_hour_index(time)?)));
+        let apparent_temperature = current_hour
+            .and_then(|(hourly, index)| hourly.apparent_temperature.get(index).copied().flatten());
+
+        let mut parts = vec![
+            format!("{}{}", current.temperature, unit_symbol),
+            self.language
+                .weather_description(current.weathercode)
+                .to_string(),
+        ];
+
+        if let Some(apparent_temperature) = apparent_temperature {
+            parts.push(
+                self.language
+                    .feels_like(&format!("{}{}", apparent_temperature, unit_symbol)),
+            );
+        }
+
+        let precipitation_probability = response.daily.as_ref().and_then(|daily| {
+            daily
+                .precipitation_probability_max
+                .first()
+                .copied()
+                .flatten()
+        });
+
+        if let Some(daily) = &response.daily {
+            let high = daily.temperature_2m_max.first().copied().flatten();
+            let low = daily.temperature_2m_min.first().copied().flatten();
+
+            if let (Some(high), Some(low)) = (high, low) {
+                parts.push(self.language.high_and_low(
+                    &format!("{}{}", high, unit_symbol),
+                    &format!("{}{}", low, unit_symbol),
+                ));
+            }
+        }
+
+        if let Some(probability) = precipitation_probability {
+            parts.push(self.language.chance_of_rain(probability));
+        }
+
+        let high_uv_index = response
+            .daily
+            .as_ref()
+            .and_then(|daily| daily.uv_index_max.first().copied().flatten())
+            .filter(|uv_index| *uv_index > self.uv_threshold);
+
+        if let Some(uv_index) = high_uv_index {
+            parts.push(self.language.high_uv_index(uv_index));
+        }
+
+        if self.include_aqi {
+            match fetch_air_quality(&self.client, latitude, longitude, self.retries).await {
+                Ok(Some(aqi)) => parts.push(self.language.air_quality(aqi)),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to fetch air quality, skipping it: {}", e),
+            }
+        }
+
+        if let Some(windspeed) = current.windspeed {
+            let windspeed_unit = units
+                .and_then(|units| units.windspeed.as_deref())
+                .unwrap_or("km/h");
+            let gusts = current_hour
+                .and_then(|(hourly, index)| hourly.wind_gusts_10m.get(index).copied().flatten())
+                .map(|gusts| format!("{} {}", gusts, windspeed_unit));
+
+            parts.push(self.language.wind(
+                &format!("{} {}", windspeed, windspeed_unit),
+                current.winddirection,
+                gusts.as_deref(),
+            ));
+        }
+
+        let humidity = current_hour
+            .and_then(|(hourly, index)| hourly.relative_humidity_2m.get(index).copied().flatten());
+
+        if let Some(humidity) = humidity {
+            parts.push(self.language.humidity(humidity));
+        }
+
+        let cloud_cover = current_hour
+            .and_then(|(hourly, index)| hourly.cloud_cover.get(index).copied().flatten());
+
+        if let Some(cloud_cover) = cloud_cover {
+            parts.push(self.language.cloud_cover(cloud_cover));
+        }
+
+        if let Some(daily) = &response.daily {
+            if let Some(sunrise) = daily
+                .sunrise
+                .first()
+                .and_then(|time| format_local_time(time.as_deref()?))
+            {
+                parts.push(self.language.sunrise(sunrise));
+            }
+
+            if let Some(sunset) = daily
+                .sunset
+                .first()
+                .and_then(|time| format_local_time(time.as_deref()?))
+            {
+                parts.push(self.language.sunset(sunset));
+            }
+        }
+
+        Ok(Weather {
+            summary: parts.join(", "),
+            code: Some(current.weathercode),
+            temperature_c: Some(self.unit.to_celsius(current.temperature)),
+            apparent_temperature_c: apparent_temperature
+                .map(|temperature| self.unit.to_celsius(temperature)),
+            precipitation_probability,
+            high_uv_index,
+        })
+    }
+}
+
+struct Wttr {
+    location: String,
+    unit: TemperatureUnit,
+    language: Language,
+    retries: u32,
+    client: reqwest::Client,
+}
+
+#[async_trait(?Send)]
+impl WeatherProvider for Wttr {
+    fn name(&self) -> &'static str {
+        "wttr"
+    }
+
+    async fn fetch(&self) -> Result<Weather, GoodMorningError> {
+        let build_error = || GoodMorningError::Weather("Failed to build wttr.in URL".to_string());
+        let mut url = Url::parse("https://wttr.in/").map_err(|_| build_error())?;
+        url.path_segments_mut()
+            .map_err(|_| build_error())?
+            .push(&self.location);
+        url.query_pairs_mut()
+            .append_pair("format", "j1")
+            .append_pair("lang", self.language.code());
+
+        let response: WttrResponse = get_with_retries(&self.client, url.as_str(), self.retries)
+            .await?
+            .json()
+            .await?;
+        let current = response.current_condition.first().ok_or_else(|| {
+            GoodMorningError::Weather("wttr.in returned no current conditions".to_string())
+        })?;
+
+        Ok(Weather {
+            summary: wttr_summary(current, self.unit, self.language),
+            code: None,
+            temperature_c: current.temp_c.trim().parse().ok(),
+            apparent_temperature_c: None,
+            precipitation_probability: None,
+            high_uv_index: None,
+        })
+    }
+}
+
+fn wttr_summary(current: &WttrCondition, unit: TemperatureUnit, language: Language) -> String {
+    let temperature = match unit {
+        TemperatureUnit::Celsius => &current.temp_c,
+        TemperatureUnit::Fahrenheit => &current.temp_f,
+    };
+
+    let mut parts = vec![format!("{}{}", temperature, unit.symbol())];
+
+    if let Some(description) = current.description(language) {
+        parts.push(description.to_lowercase());
+    }
+
+    if let Some(windspeed) = &current.windspeed_kmph {
+        parts.push(language.wind(&format!("{} km/h", windspeed), None, None));
+    }
+
+    if let Some(humidity) = current
+        .humidity
+        .as_deref()
+        .and_then(|humidity| humidity.trim().parse().ok())
+    {
+        parts.push(language.humidity(humidity));
+    }
+
+    parts.join(", ")
+}
+
+fn weather_sources() -> Result<Vec<WeatherSource>, GoodMorningError> {
+    let names = env::var("GOOD_MORNING_WEATHER_PROVIDERS")
+        .unwrap_or_else(|_| DEFAULT_WEATHER_PROVIDERS.to_string());
+
+    let sources = names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| match name.to_lowercase().as_str() {
+            "open-meteo" => Ok(WeatherSource::OpenMeteo),
+            "wttr" => Ok(WeatherSource::Wttr),
+            _ => Err(GoodMorningError::Config(format!(
+                "Unknown weather provider '{}'",
+                name
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if sources.is_empty() {
+        return Err(GoodMorningError::Config(
+            "GOOD_MORNING_WEATHER_PROVIDERS must name at least one of: open-meteo, wttr"
+                .to_string(),
+        ));
+    }
+
+    Ok(sources)
+}
+
+fn weather_providers(
+    config: &Config,
+    location: Option<(f64, f64)>,
+) -> Vec<Box<dyn WeatherProvider>> {
+    let city = config.city.clone().filter(|_| location.is_none());
+    let (latitude, longitude) = location.unwrap_or((config.latitude, config.longitude));
+
+    config
+        .weather_sources
+        .iter()
+        .map(|source| -> Box<dyn WeatherProvider> {
+            match source {
+                WeatherSource::OpenMeteo => {
+                    Box::new(open_meteo(config, city.clone(), latitude, longitude))
+                }
+                WeatherSource::Wttr => Box::new(Wttr {
+                    location: city
+                        .clone()
+                        .unwrap_or_else(|| format!("{},{}", latitude, longitude)),
+                    unit: config.temperature_unit,
+                    language: config.weather_language,
+                    retries: config.weather_retries,
+                    client: config.http.clone(),
+                }),
+            }
+        })
+        .collect()
+}
+
+fn open_meteo(config: &Config, city: Option<String>, latitude: f64, longitude: f64) -> OpenMeteo {
+    OpenMeteo {
+        city,
+        latitude,
+        longitude,
+        timezone: config.weather_timezone.clone(),
+        unit: config.temperature_unit,
+        language: config.weather_language,
+        uv_threshold: config.uv_threshold,
+        include_aqi: config.include_aqi,
+        retries: config.weather_retries,
+        client: config.http.clone(),
+    }
+}
+
+pub async fn get_weather(
+    config: &Config,
+    location: Option<(f64, f64)>,
+) -> Result<Weather, GoodMorningError> {
+    recent_weather(config, &weather_providers(config, location), location).await
+}
+
+/// Reuses weather fetched within `GOOD_MORNING_WEATHER_TTL_SECS` before asking
+/// `providers` again.
+#[instrument(skip_all)]
+async fn recent_weather(
+    config: &Config,
+    providers: &[Box<dyn WeatherProvider>],
+    location: Option<(f64, f64)>,
+) -> Result<Weather, GoodMorningError> {
+    let key = location
+        .map(|(latitude, longitude)| format!("{},{}", latitude, longitude))
+        .unwrap_or_default();
+    let recent = config
+        .recent_weather
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .filter(|(fetched_at, _)| fetched_at.elapsed() < config.weather_ttl)
+        .map(|(_, weather)| weather.clone());
+
+    if let Some(weather) = recent {
+        debug!(weather = %weather.summary, "Reusing recently fetched weather");
+        return Ok(weather);
+    }
+
+    let weather = fetch_weather(config, providers, location).await?;
+
+    if !config.weather_ttl.is_zero() {
+        config
+            .recent_weather
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, (Instant::now(), weather.clone()));
+    }
+
+    Ok(weather)
+}
+
+async fn fetch_weather(
+    config: &Config,
+    providers: &[Box<dyn WeatherProvider>],
+    location: Option<(f64, f64)>,
+) -> Result<Weather, GoodMorningError> {
+    let mut errors = Vec::new();
+
+    for provider in providers {
+        match provider.fetch().await {
+            Ok(weather) => {
+                info!(provider = provider.name(), weather = %weather.summary, "Weather fetched");
+
+                if location.is_none() {
+                    if let Err(e) =
+                        write_weather_cache(&config.cache_dir, &weather_cache_key(config), &weather)
+                    {
+                        warn!("Failed to write weather cache: {}", e);
+                    }
+                }
+
+                return Ok(weather);
+            }
+            Err(e) => {
+                warn!("Weather provider '{}' failed: {}", provider.name(), e);
+                errors.push(format!("{}: {}", provider.name(), e));
+            }
+        }
+    }
+
+    if let Some(weather) = read_weather_cache(&config.cache_dir, &weather_cache_key(config))
+        .filter(|_| location.is_none())
+    {
+        info!("Using cached weather: {}", weather.summary);
+        return Ok(weather);
+    }
+
+    if errors.is_empty() {
+        return Err(GoodMorningError::Weather(
+            "No weather providers configured".to_string(),
+        ));
+    }
+
+    Err(GoodMorningError::Weather(format!(
+        "All weather providers failed ({})",
+        errors.join("; ")
+    )))
+}
+
+fn format_local_time(iso_time: &str) -> Option<&str> {
+    iso_time.split_once('T').and_then(|(_, time)| time.get(..5))
+}
+
+fn weather_cache_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join(WEATHER_CACHE_FILE)
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn weather_cache_key(config: &Config) -> String {
+    format!(
+        "{}|{},{}|{}|{}",
+        config.city.as_deref().unwrap_or_default(),
+        config.latitude,
+        config.longitude,
+        config.temperature_unit.query_param(),
+        config.weather_language.code()
+    )
+}
+
+fn write_weather_cache(
+    cache_dir: &Path,
+    key: &str,
+    weather: &Weather,
+) -> Result<(), GoodMorningError> {
+    let path = weather_cache_path(cache_dir);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let cache = WeatherCache {
+        timestamp: unix_timestamp(),
+        key: key.to_string(),
+        weather: weather.summary.clone(),
+        code: weather.code,
+        temperature_c: weather.temperature_c,
+        apparent_temperature_c: weather.apparent_temperature_c,
+        precipitation_probability: weather.precipitation_probability,
+        high_uv_index: weather.high_uv_index,
+    };
+
+    fs::write(path, serde_json::to_string(&cache)?)?;
+    Ok(())
+}
+
+fn read_weather_cache(cache_dir: &Path, key: &str) -> Option<Weather> {
+    let contents = fs::read_to_string(weather_cache_path(cache_dir)).ok()?;
+    let cache: WeatherCache = serde_json::from_str(&contents).ok()?;
+
+    if cache.key != key {
+        return None;
+    }
+
+    (unix_timestamp().saturating_sub(cache.timestamp) < WEATHER_CACHE_MAX_AGE.as_secs()).then_some(
+        Weather {
+            summary: cache.weather,
+            code: cache.code,
+            temperature_c: cache.temperature_c,
+            apparent_temperature_c: cache.apparent_temperature_c,
+            precipitation_probability: cache.precipitation_probability,
+            high_uv_index: cache.high_uv_index,
+        },
+    )
+}
+
+fn map_weather_code_to_icon(code: i32) -> &'static str {
+    match code {
+        0 => "01d",
+        1 => "02d",
+        2 => "03d",
+        3 => "04d",
+        45 | 48 => "50d",
+        51..=57 | 80..=82 => "09d",
+        61..=67 => "10d",
+        71..=77 | 85 | 86 => "13d",
+        95..=99 => "11d",
+        _ => "50d",
+    }
+}
+
+async fn fetch_weather_icon(
+    client: &reqwest::Client,
+    code: i32,
+) -> Result<Attachment, GoodMorningError> {
+    let url = format!(
+        "https://openweathermap.org/img/wn/{}@2x.png",
+        map_weather_code_to_icon(code)
+    );
+    let data = get_with_retries(client, &url, 0).await?.bytes().await?;
+
+    Ok(Attachment {
+        filename: WEATHER_ICON_FILE.to_string(),
+        data: data.to_vec(),
+    })
+}
+
+fn map_weather_code_to_color(code: Option<i32>) -> u32 {
+    match code {
+        Some(0 | 1) => 0xF1C40F,
+        Some(2 | 3) => 0x95A5A6,
+        Some(45 | 48) => 0xBDC3C7,
+        Some(51..=67 | 80..=82) => 0x3498DB,
+        Some(71..=77 | 85 | 86) => 0xECF0F1,
+        Some(95..=99) => 0x9B59B6,
+        _ => 0x5865F2,
+    }
+}
+
+fn map_weather_code_to_emoji(code: i32) -> &'static str {
+    match code {
+        0 => "☀️",
+        1 => "🌤️",
+        2 => "⛅",
+        3 => "☁️",
+        45 | 48 => "🌫️",
+        51..=57 => "🌦️",
+        61..=67 => "🌧️",
+        71..=77 => "❄️",
+        80..=82 => "🌦️",
+        85 | 86 => "🌨️",
+        95..=99 => "⛈️",
+        _ => "🌡️",
+    }
+}
+
+fn compass_direction(degrees: f32) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+
+    POINTS[compass_index(degrees)]
+}
+
+fn compass_direction_in_russian(degrees: f32) -> &'static str {
+    const POINTS: [&str; 8] = ["С", "СВ", "В", "ЮВ", "Ю", "ЮЗ", "З", "СЗ"];
+
+    POINTS[compass_index(degrees)]
+}
+
+fn compass_index(degrees: f32) -> usize {
+    (degrees.rem_euclid(360.0) / 45.0).round() as usize % 8
+}
+
+fn map_weather_code_to_description(code: i32) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 => "fog",
+        48 => "depositing rime fog",
+        51 => "light drizzle",
+        53 => "moderate drizzle",
+        55 => "dense drizzle",
+        56 => "light freezing drizzle",
+        57 => "dense freezing drizzle",
+        61 => "slight rain",
+        63 => "moderate rain",
+        65 => "heavy rain",
+        66 => "light freezing rain",
+        67 => "heavy freezing rain",
+        71 => "slight snowfall",
+        73 => "moderate snowfall",
+        75 => "heavy snowfall",
+        77 => "snow grains",
+        80 => "slight rain showers",
+        81 => "moderate rain showers",
+        82 => "violent rain showers",
+        85 => "slight snow showers",
+        86 => "heavy snow showers",
+        95 => "thunderstorm",
+        96 => "thunderstorm with slight hail",
+        99 => "thunderstorm with heavy hail",
+        _ => "unknown weather",
+    }
+}
+
+fn map_weather_code_to_russian_description(code: i32) -> &'static str {
+    match code {
+        0 => "ясно",
+        1 => "преимущественно ясно",
+        2 => "переменная облачность",
+        3 => "пасмурно",
+        45 => "туман",
+        48 => "туман с изморозью",
+        51 => "слабая морось",
+        53 => "умеренная морось",
+        55 => "сильная морось",
+        56 => "слабая ледяная морось",
+        57 => "сильная ледяная морось",
+        61 => "небольшой дождь",
+        63 => "умеренный дождь",
+        65 => "сильный дождь",
+        66 => "слабый ледяной дождь",
+        67 => "сильный ледяной дождь",
+        71 => "небольшой снег",
+        73 => "умеренный снег",
+        75 => "сильный снег",
+        77 => "снежная крупа",
+        80 => "небольшой ливень",
+        81 => "умеренный ливень",
+        82 => "сильный ливень",
+        85 => "небольшой снегопад",
+        86 => "сильный снегопад",
+        95 => "гроза",
+        96 => "гроза с небольшим градом",
+        99 => "гроза с сильным градом",
+        _ => "неизвестная погода",
+    }
+}
+
+fn ollama_url() -> Result<Url, GoodMorningError> {
+    let host = env::var("GOOD_MORNING_OLLAMA_HOST")
+        .ok()
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .map(|host| {
+            if host.contains("://") {
+                host
+            } else {
+                format!("http://{}", host)
+            }
+        })
+        .unwrap_or_else(|| DEFAULT_OLLAMA_HOST.to_string());
+    let port = parse_env_or("GOOD_MORNING_OLLAMA_PORT", DEFAULT_OLLAMA_PORT)?;
+
+    let mut url = Url::parse(&host).map_err(|e| {
+        GoodMorningError::Config(format!(
+            "Failed to parse GOOD_MORNING_OLLAMA_HOST '{}': {}",
+            host, e
+        ))
+    })?;
+    url.set_port(Some(port)).map_err(|_| {
+        GoodMorningError::Config(format!(
+            "GOOD_MORNING_OLLAMA_HOST '{}' cannot have a port",
+            host
+        ))
+    })?;
+
+    Ok(url)
+}
+
+fn is_local_host(url: &Url) -> bool {
+    matches!(
+        url.host_str(),
+        Some("localhost" | "127.0.0.1" | "::1" | "[::1]")
+    )
+}
+
+fn llm_backend_kind() -> Result<LlmBackendKind, GoodMorningError> {
+    match env::var("GOOD_MORNING_LLM_BACKEND")
+        .map(|backend| backend.trim().to_lowercase())
+        .as_deref()
+    {
+        Ok("openai") => {}
+        Ok("template") => return Ok(LlmBackendKind::Template),
+        Ok("ollama") | Ok("") | Err(_) => return Ok(LlmBackendKind::Ollama),
+        Ok(backend) => {
+            return Err(GoodMorningError::Config(format!(
+            "Unsupported GOOD_MORNING_LLM_BACKEND '{}', expected one of: ollama, openai, template",
+            backend
+        )))
+        }
+    }
+
+    let non_empty = |var: &str| {
+        env::var(var)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(LlmBackendKind::OpenAi(OpenAi {
+        base_url: non_empty("GOOD_MORNING_OPENAI_BASE_URL")
+            .unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.to_string()),
+        api_key: non_empty("GOOD_MORNING_OPENAI_API_KEY"),
+        model: non_empty("GOOD_MORNING_OPENAI_MODEL")
+            .ok_or(GoodMorningError::MissingEnv("GOOD_MORNING_OPENAI_MODEL"))?,
+    }))
+}
+
+fn ollama_model(model: &str) -> Result<String, GoodMorningError> {
+    match model.trim() {
+        "" => Err(GoodMorningError::Config(
+            "GOOD_MORNING_OLLAMA_MODEL must not be empty".to_string(),
+        )),
+        model => Ok(model.to_string()),
+    }
+}
+
+fn prompt_template(has_system_prompt: bool) -> Result<String, GoodMorningError> {
+    if let Ok(path) = env::var("GOOD_MORNING_PROMPT_FILE") {
+        return fs::read_to_string(&path).map_err(|e| {
+            GoodMorningError::Config(format!("Failed to read prompt file '{}': {}", path, e))
+        });
+    }
+
+    let default = if has_system_prompt {
+        DEFAULT_DATA_PROMPT
+    } else {
+        DEFAULT_PROMPT
+    };
+
+    Ok(env::var("GOOD_MORNING_PROMPT").unwrap_or_else(|_| default.to_string()))
+}
+
+fn weekend_prompt_template() -> Result<Option<String>, GoodMorningError> {
+    if let Ok(path) = env::var("GOOD_MORNING_PROMPT_WEEKEND_FILE") {
+        return fs::read_to_string(&path).map(Some).map_err(|e| {
+            GoodMorningError::Config(format!(
+                "Failed to read weekend prompt file '{}': {}",
+                path, e
+            ))
+        });
+    }
+
+    Ok(env::var("GOOD_MORNING_PROMPT_WEEKEND")
+        .ok()
+        .filter(|prompt| !prompt.trim().is_empty()))
+}
+
+fn system_prompt() -> Option<String> {
+    env::var("GOOD_MORNING_SYSTEM_PROMPT")
+        .ok()
+        .filter(|prompt| !prompt.trim().is_empty())
+}
+
+fn render_template(
+    template: &str,
+    language: Language,
+    members: &[Member],
+    weather: &Weather,
+) -> String {
+    let names = members
+        .iter()
+        .map(|member| member.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let clothing = match weather.clothing_temperature_c() {
+        Some(temp_c) => clothing_suggestion(
+            temp_c,
+            weather.code.unwrap_or(-1),
+            weather.precipitation_probability,
+            weather.high_uv_index,
+        ),
+        None => DEFAULT_CLOTHING_SUGGESTION.to_string(),
+    };
+
+    template
+        .replace("{members}", &names)
+        .replace("{weather}", &weather.summary)
+        .replace("{clothing}", &clothing)
+        .replace("{language}", language.name())
+}
+
+pub fn clothing_suggestion(
+    temp_c: f32,
+    weather_code: i32,
+    precipitation_probability: Option<f32>,
+    high_uv_index: Option<f32>,
+) -> String {
+    let mut advice = vec![CLOTHING[clothing_band(temp_c)]];
+    advice.extend(
+        clothing_extra(
+            temp_c,
+            weather_code,
+            precipitation_probability,
+            high_uv_index,
+        )
+        .map(|extra| CLOTHING_EXTRAS[extra]),
+    );
+
+    format!("wear {}", advice.join(", and bring "))
+}
+
+fn clothing_band(temp_c: f32) -> usize {
+    CLOTHING_BANDS_C
+        .iter()
+        .take_while(|limit| temp_c >= **limit)
+        .count()
+}
+
+fn clothing_extra(
+    temp_c: f32,
+    weather_code: i32,
+    precipitation_probability: Option<f32>,
+    high_uv_index: Option<f32>,
+) -> Option<usize> {
+    let likely_rain = precipitation_probability
+        .is_some_and(|probability| probability >= UMBRELLA_PRECIPITATION_PROBABILITY);
+
+    match weather_code {
+        51..=67 | 80..=82 | 95..=99 => Some(0),
+        71..=77 | 85 | 86 => Some(1),
+        _ if likely_rain => Some(0),
+        0 | 1 if temp_c >= 25.0 => Some(2),
+        _ if high_uv_index.is_some() => Some(2),
+        _ => None,
+    }
+}
+
+pub fn template_greeting(members: &[Member], weather: &Weather) -> String {
+    let names = match members {
+        [] => "друзья".to_string(),
+        [member] => member.name.clone(),
+        [rest @ .., last] => format!(
+            "{} и {}",
+            rest.iter()
+                .map(|member| member.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            last.name
+        ),
+    };
+
+    let description = weather.code.map(map_weather_code_to_russian_description);
+    let mut conditions = match (description, weather.temperature_c) {
+        (Some(description), Some(temp_c)) => format!("Сегодня {}, {:.0}°C", description, temp_c),
+        (Some(description), None) => format!("Сегодня {}", description),
+        (None, Some(temp_c)) => format!("Сегодня {:.0}°C", temp_c),
+        (None, None) => format!("Погода на сегодня: {}", weather.summary),
+    };
+
+    if let (Some(_), Some(apparent_c)) = (weather.temperature_c, weather.apparent_temperature_c) {
+        conditions.push_str(&format!(", ощущается как {:.0}°C", apparent_c));
+    }
+
+    if let (Some(_), Some(probability)) = (weather.temperature_c, weather.precipitation_probability)
+    {
+        conditions.push_str(&format!(", вероятность дождя {:.0}%", probability));
+    }
+
+    let mut sentences = vec![
+        format!("Доброе утро, {}!", names),
+        format!("{}.", conditions),
+    ];
+
+    if let Some(temp_c) = weather.clothing_temperature_c() {
+        let mut clothing = format!("Наденьте {}", CLOTHING_RU[clothing_band(temp_c)]);
+
+        if let Some(extra) = clothing_extra(
+            temp_c,
+            weather.code.unwrap_or(-1),
+            weather.precipitation_probability,
+            weather.high_uv_index,
+        ) {
+            clothing.push_str(&format!(" и не забудьте {}", CLOTHING_EXTRAS_RU[extra]));
+        }
+
+        sentences.push(format!("{}.", clothing));
+    }
+
+    sentences.push("Хорошего вам дня!".to_string());
+    sentences.join(" ")
+}
+
+fn generation_options() -> Result<Option<GenerationOptions>, GoodMorningError> {
+    let temperature = parse_env::<f32>("GOOD_MORNING_TEMPERATURE")?;
+    let top_p = parse_env::<f32>("GOOD_MORNING_TOP_P")?;
+    let top_k = parse_env::<u32>("GOOD_MORNING_TOP_K")?;
+    let seed = parse_env::<i32>("GOOD_MORNING_SEED")?;
+
+    if temperature.is_none() && top_p.is_none() && top_k.is_none() && seed.is_none() {
+        return Ok(None);
+    }
+
+    let mut options = GenerationOptions::default();
+
+    if let Some(temperature) = temperature {
+        options = options.temperature(temperature);
+    }
+
+    if let Some(top_p) = top_p {
+        options = options.top_p(top_p);
+    }
+
+    if let Some(top_k) = top_k {
+        options = options.top_k(top_k);
+    }
+
+    if let Some(seed) = seed {
+        options = options.seed(seed);
+    }
+
+    Ok(Some(options))
+}
+
+async fn wait_for_ollama(
+    client: &reqwest::Client,
+    ollama: &Ollama,
+    ready_timeout: Duration,
+) -> Result<(), GoodMorningError> {
+    let deadline = Instant::now() + ready_timeout;
+
+    loop {
+        match client.get(ollama.url_str()).send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            _ if Instant::now() >= deadline => {
+                return Err(GoodMorningError::Llm(format!(
+                    "Ollama at {} did not become ready within {}s",
+                    ollama.url_str(),
+                    ready_timeout.as_secs()
+                )));
+            }
+            _ => {
+                info!("Waiting for Ollama...");
+                sleep(OLLAMA_READY_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn ensure_model(ollama: &Ollama, model: &str) -> Result<(), GoodMorningError> {
+    let tagged_model = if model.contains(':') {
+        model.to_string()
+    } else {
+        format!("{}:latest", model)
+    };
+
+    let installed = ollama
+        .list_local_models()
+        .await
+        .map_err(|e| GoodMorningError::Llm(format!("Failed to list Ollama models: {}", e)))?
+        .iter()
+        .any(|local| local.name == model || local.name == tagged_model);
+
+    if installed {
+        return Ok(());
+    }
+
+    info!("Model '{}' not found locally, pulling...", model);
+
+    let mut stream = ollama
+        .pull_model_stream(model.to_string(), false)
+        .await
+        .map_err(|e| GoodMorningError::Llm(format!("Failed to pull model '{}': {}", model, e)))?;
+    let mut last_line = String::new();
+
+    while let Some(status) = stream.next().await {
+        let status = status.map_err(|e| {
+            GoodMorningError::Llm(format!("Failed to pull model '{}': {}", model, e))
+        })?;
+        let line = match (status.completed, status.total) {
+            (Some(completed), Some(total)) if total > 0 => {
+                format!("{} {}%", status.message, completed * 100 / total)
+            }
+            _ => status.message,
+        };
+
+        if line != last_line {
+            info!("{}", line);
+            last_line = line;
+        }
+    }
+
+    info!("Model '{}' pulled successfully", model);
+    Ok(())
+}
+
+async fn generate_once(
+    ollama: &Ollama,
+    request: GenerationRequest<'_>,
+    stream: bool,
+) -> Result<String, GoodMorningError> {
+    if !stream {
+        return Ok(ollama.generate(request).await?.response);
+    }
+
+    let mut stream = ollama.generate_stream(request).await?;
+    let mut response = String::new();
+    let mut stdout = io::stdout();
+
+    while let Some(chunk) = stream.next().await {
+        for part in chunk? {
+            print!("{}", part.response);
+            stdout.flush()?;
+            response.push_str(&part.response);
+        }
+    }
+
+    println!();
+    Ok(response)
+}
+
+#[async_trait(?Send)]
+pub trait LlmBackend {
+    fn name(&self) -> &'static str;
+
+    async fn generate(&self, prompt: &str) -> Result<String, GoodMorningError>;
+}
+
+struct OllamaBackend<'a> {
+    ollama: Ollama,
+    config: &'a Config,
+}
+
+#[async_trait(?Send)]
+impl LlmBackend for OllamaBackend<'_> {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, GoodMorningError> {
+        let mut request = GenerationRequest::new(self.config.ollama_model.clone(), prompt);
+
+        if let Some(system) = &self.config.system_prompt {
+            request = request.system(system.clone());
+        }
+
+        if let Some(options) = &self.config.generation_options {
+            request = request.options(options.clone());
+        }
+
+        generate_once(&self.ollama, request, self.config.stream)
+            .await
+            .map_err(|e| GoodMorningError::Llm(format!("Ollama generation failed: {}", e)))
+    }
+}
+
+struct OpenAiBackend<'a> {
+    openai: &'a OpenAi,
+    config: &'a Config,
+}
+
+#[async_trait(?Send)]
+impl LlmBackend for OpenAiBackend<'_> {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, GoodMorningError> {
+        let mut messages = Vec::new();
+
+        if let Some(system) = &self.config.system_prompt {
+            messages.push(serde_json::json!({ "role": "system", "content": system }));
+        }
+
+        messages.push(serde_json::json!({ "role": "user", "content": prompt }));
+
+        let url = format!(
+            "{}/chat/completions",
+            self.openai.base_url.trim_end_matches('/')
+        );
+        let mut request = self.config.http.post(url).json(&serde_json::json!({
+            "model": self.openai.model,
+            "messages": messages
+        }));
+
+        if let Some(api_key) = &self.openai.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: ChatCompletionResponse = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| GoodMorningError::Llm(format!("Chat completion request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| {
+                GoodMorningError::Llm(format!("Invalid chat completion response: {}", e))
+            })?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| GoodMorningError::Llm("Chat completion returned no choices".to_string()))
+    }
+}
+
+#[instrument(skip_all, fields(backend = backend.name()))]
+pub async fn generate_greeting(
+    backend: &dyn LlmBackend,
+    config: &Config,
+    members: &[Member],
+    weather: &Weather,
+) -> Result<String, GoodMorningError> {
+    let template = config
+        .weekend_prompt
+        .as_deref()
+        .filter(|_| is_weekend(Local::now()))
+        .unwrap_or(&config.prompt);
+    let prompt = render_template(template, config.language, members, weather);
+    debug!(system = ?config.system_prompt, %prompt, "Prompt rendered");
+
+    if config.debug_prompt {
+        if let Some(system) = &config.system_prompt {
+            println!("[debug prompt] System:\n{}", system);
+        }
+        println!("[debug prompt] Prompt:\n{}", prompt);
+    }
+
+    let retries = config.llm_retries;
+    let mut attempt = 0;
+
+    loop {
+        info!(attempt, "Prompt sent");
+
+        let result = match timeout(config.llm_timeout, backend.generate(&prompt)).await {
+            Ok(result) => result,
+            Err(_) => Err(GoodMorningError::Llm(format!(
+                "Greeting generation timed out after {}s",
+                config.llm_timeout.as_secs()
+            ))),
+        };
+
+        match result {
+            Ok(response) => {
+                info!("Greeting generated");
+                debug!(%response, "Raw model response");
+
+                if config.debug_prompt {
+                    println!("[debug prompt] Response:\n{}", response);
+                }
+
+                return Ok(response.trim().to_string());
+            }
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                warn!(
+                    "Greeting generation failed: {}, retrying in {}s ({}/{})...",
+                    e,
+                    LLM_RETRY_DELAY.as_secs(),
+                    attempt,
+                    retries
+                );
+                sleep(LLM_RETRY_DELAY).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn fallback_greeting(config: &Config, members: &[Member], weather: &Weather) -> String {
+    let template = match (&config.fallback_template, config.language) {
+        (Some(template), _) => template.as_str(),
+        (None, Language::Russian) => return template_greeting(members, weather),
+        (None, language) => language.fallback_template(),
+    };
+
+    render_template(template, config.language, members, weather)
+}
+
+fn order_mentions(members: &[Member], order: MentionOrder, seed: Option<u64>) -> Vec<Member> {
+    let mut members = members.to_vec();
+
+    match order {
+        MentionOrder::Config => {}
+        MentionOrder::Alphabetical => members.sort_by_key(|member| member.name.to_lowercase()),
+        MentionOrder::Shuffle => {
+            let mut rng = match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => rand::make_rng(),
+            };
+            members.shuffle(&mut rng);
+        }
+    }
+
+    members
+}
+
+fn format_mentions(members: &[Member]) -> String {
+    members
+        .iter()
+        .map(Member::mention)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn format_message(
+    members: &[Member],
+    generated_message: &str,
+    footer: Option<&Footer>,
+) -> String {
+    let mentions = format_mentions(members);
+    let footer_text = footer.map(Footer::render).unwrap_or_default();
+    let parts = match footer {
+        Some(footer) if footer.before_mentions => [generated_message, &footer_text, &mentions],
+        _ => [generated_message, &mentions, &footer_text],
+    };
+
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait(?Send)]
+pub trait MessageSink {
+    async fn send(
+        &self,
+        members: &[Member],
+        message: &str,
+        embed: Option<&Embed>,
+        icon: Option<&Attachment>,
+    ) -> Result<(), GoodMorningError>;
+}
+
+struct DiscordSink<'a> {
+    config: &'a Config,
+    delivery: &'a Delivery,
+}
+
+#[async_trait(?Send)]
+impl MessageSink for DiscordSink<'_> {
+    async fn send(
+        &self,
+        members: &[Member],
+        message: &str,
+        embed: Option<&Embed>,
+        icon: Option<&Attachment>,
+    ) -> Result<(), GoodMorningError> {
+        let outgoing = Outgoing {
+            content: message,
+            embed,
+            icon,
+            members,
+            reaction: self.config.reaction.as_deref(),
+        };
+
+        send_message(
+            &self.config.http,
+            self.delivery,
+            &outgoing,
+            self.config.discord_retries,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+struct DryRunSink<'a> {
+    delivery: &'a Delivery,
+}
+
+#[async_trait(?Send)]
+impl MessageSink for DryRunSink<'_> {
+    async fn send(
+        &self,
+        members: &[Member],
+        message: &str,
+        embed: Option<&Embed>,
+        icon: Option<&Attachment>,
+    ) -> Result<(), GoodMorningError> {
+        let target = match self.delivery {
+            Delivery::Dm { .. } => format!(
+                "a DM to {}",
+                members
+                    .iter()
+                    .map(|member| member.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            delivery => delivery_target(delivery),
+        };
+
+        print_dry_run(&target, message, embed, icon);
+        Ok(())
+    }
+}
+
+#[instrument(skip_all)]
+async fn send_message(
+    client: &reqwest::Client,
+    delivery: &Delivery,
+    outgoing: &Outgoing<'_>,
+    retries: u32,
+) -> Result<Vec<String>, GoodMorningError> {
+    let (token, channel_ids, edit_message_id, daily_thread) = match delivery {
+        Delivery::Bot {
+            token,
+            channel_ids,
+            edit_message_id,
+            daily_thread,
+        } => (token, channel_ids, *edit_message_id, *daily_thread),
+        Delivery::Webhook(webhook) => {
+            send_to_webhook(&discord_http(client, ""), webhook, outgoing, retries)
+                .await
+                .map_err(|e| GoodMorningError::Discord(e.to_string()))?;
+            info!("Message delivered via webhook");
+            return Ok(Vec::new());
+        }
+        Delivery::Dm { token } => {
+            send_direct_messages(&discord_http(client, token), outgoing, retries).await?;
+            return Ok(Vec::new());
+        }
+    };
+
+    let http = discord_http(client, token);
+    let mut message_ids = Vec::new();
+    let mut failures = Vec::new();
+
+    for &channel_id in channel_ids {
+        let result = if daily_thread {
+            match open_daily_thread(&http, channel_id, retries).await {
+                Ok(thread_id) => send_to_channel(&http, thread_id, None, outgoing, retries).await,
+                Err(e) => Err(e),
+            }
+        } else {
+            send_to_channel(&http, channel_id, edit_message_id, outgoing, retries).await
+        };
+
+        match result {
+            Ok(message_id) => {
+                info!(channel_id = %channel_id, message_id = %message_id, "Message delivered");
+                message_ids.push(message_id);
+            }
+            Err(e) => {
+                error!("Failed to send message to channel {}: {}", channel_id, e);
+                failures.push(format!("{}: {}", channel_id, e));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(GoodMorningError::Discord(format!(
+            "Failed to send message to {} of {} channels ({})",
+            failures.len(),
+            channel_ids.len(),
+            failures.join("; ")
+        )));
+    }
+
+    Ok(message_ids)
+}
+
+fn discord_http(client: &reqwest::Client, token: &str) -> Http {
+    HttpBuilder::new(token)
+        .client(client.clone())
+        .ratelimiter_disabled(true)
+        .build()
+}
+
+/// Runs a Discord request, retrying server errors up to `retries` times and
+/// waiting out 429s until [`DISCORD_MAX_RETRY_AFTER`] has been spent. The outer
+/// error is returned once the rate limit outlasts that cap.
+async fn with_discord_retries<T, F, R>(
+    retries: u32,
+    mut request: F,
+) -> Result<serenity::Result<T>, GoodMorningError>
+where
+    F: FnMut() -> R,
+    R: Future<Output = serenity::Result<T>>,
+{
+    let mut server_errors = 0;
+    let mut rate_limits = 0;
+    let mut waited = Duration::ZERO;
+
+    loop {
+        match request().await {
+            Err(serenity::Error::Http(e))
+                if e.status_code() == Some(StatusCode::TOO_MANY_REQUESTS) =>
+            {
+                let delay = rate_limit_delay(rate_limits, waited)?;
+                rate_limits += 1;
+                waited += delay;
+                warn!(
+                    "Discord rate limited the request, retrying in {}s...",
+                    delay.as_secs()
+                );
+                sleep(delay).await;
+            }
+            Err(serenity::Error::Http(e)) if server_errors < retries => {
+                let Some(status) = e.status_code().filter(StatusCode::is_server_error) else {
+                    return Ok(Err(serenity::Error::Http(e)));
+                };
+
+                let delay = Duration::from_secs(1 << server_errors.min(6));
+                server_errors += 1;
+                warn!(
+                    "Discord returned {}, retrying in {}s ({}/{})...",
+                    status,
+                    delay.as_secs(),
+                    server_errors,
+                    retries
+                );
+                sleep(delay).await;
+            }
+            result => return Ok(result),
+        }
+    }
+}
+
+/// Serenity drops the headers of failed requests, so the Retry-After of a 429
+/// is not available here; the wait starts at [`DEFAULT_RETRY_AFTER`] and
+/// doubles on each consecutive 429.
+fn rate_limit_delay(rate_limits: u32, waited: Duration) -> Result<Duration, GoodMorningError> {
+    let delay = DEFAULT_RETRY_AFTER * (1 << rate_limits.min(6));
+
+    if waited + delay > DISCORD_MAX_RETRY_AFTER {
+        return Err(GoodMorningError::Discord(format!(
+            "rate limited, retry after {}s",
+            delay.as_secs()
+        )));
+    }
+
+    Ok(delay)
+}
+
+fn is_not_found(error: &serenity::Error) -> bool {
+    matches!(error, serenity::Error::Http(e) if e.status_code() == Some(StatusCode::NOT_FOUND))
+}
+
+async fn send_to_channel(
+    http: &Http,
+    channel_id: ChannelId,
+    edit_message_id: Option<u64>,
+    outgoing: &Outgoing<'_>,
+    retries: u32,
+) -> Result<String, GoodMorningError> {
+    let allowed_mentions = allowed_mentions(outgoing.members);
+    let create_embed = create_embed(outgoing);
+    let create_attachment = create_attachment(outgoing);
+
+    let chunks = message_chunks(outgoing.content);
+    let last = chunks.len() - 1;
+
+    if let Some(message_id) = edit_message_id.and_then(NonZeroU64::new) {
+        if let [chunk] = chunks.as_slice() {
+            let mut builder = EditMessage::new()
+                .content(chunk)
+                .allowed_mentions(allowed_mentions.clone());
+
+            if let Some(create_embed) = &create_embed {
+                builder = builder.embed(create_embed.clone());
+            }
+
+            if let Some(create_attachment) = &create_attachment {
+                builder = builder.new_attachment(create_attachment.clone());
+            }
+
+            let result = with_discord_retries(retries, || {
+                channel_id.edit_message(http, MessageId::from(message_id), builder.clone())
+            })
+            .await?;
+
+            match result {
+                Ok(message) => {
+                    info!(channel_id = %channel_id, message_id, "Message edited");
+                    react(http, channel_id, message.id, outgoing).await;
+                    return Ok(message.id.to_string());
+                }
+                Err(e) if is_not_found(&e) => {
+                    warn!(
+                        "Message {} no longer exists in channel {}, posting a new one",
+                        message_id, channel_id
+                    );
+                }
+                Err(e) => {
+                    return Err(GoodMorningError::Discord(format!(
+                        "Failed to edit message {}: {}",
+                        message_id, e
+                    )))
+                }
+            }
+        } else {
+            warn!(
+                "Greeting needs {} messages and cannot replace message {}, posting instead",
+                chunks.len(),
+                message_id
+            );
+        }
+    }
+
+    let mut posted_ids = Vec::new();
+
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let mut builder = CreateMessage::new()
+            .content(chunk)
+            .allowed_mentions(allowed_mentions.clone());
+
+        if index == last {
+            if let Some(create_embed) = &create_embed {
+                builder = builder.embed(create_embed.clone());
+            }
+
+            if let Some(create_attachment) = &create_attachment {
+                builder = builder.add_file(create_attachment.clone());
+            }
+        }
+
+        let message =
+            with_discord_retries(retries, || channel_id.send_message(http, builder.clone()))
+                .await?
+                .map_err(|e| GoodMorningError::Discord(format!("Failed to send message: {}", e)))?;
+
+        posted_ids.push(message.id);
+    }
+
+    let last_id = posted_ids.last().copied().ok_or_else(|| {
+        GoodMorningError::Discord("Discord accepted no messages for the greeting".to_string())
+    })?;
+    react(http, channel_id, last_id, outgoing).await;
+
+    if let Some(id) = posted_ids.first().filter(|_| edit_message_id.is_some()) {
+        info!(
+            channel_id = %channel_id,
+            message_id = %id,
+            "Posted a new message, set GOOD_MORNING_EDIT_MESSAGE_ID={} to keep editing it",
+            id
+        );
+    }
+
+    Ok(last_id.to_string())
+}
+
+async fn react(http: &Http, channel_id: ChannelId, message_id: MessageId, outgoing: &Outgoing<'_>) {
+    let Some(emoji) = outgoing.reaction else {
+        return;
+    };
+
+    let result = match ReactionType::try_from(emoji) {
+        Ok(reaction) => channel_id
+            .create_reaction(http, message_id, reaction)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(e) = result {
+        warn!(
+            "Failed to react with {} to message {}: {}",
+            emoji, message_id, e
+        );
+    }
+}
+
+/// Sends `outgoing` privately to each of its members; the DM itself mentions
+/// no one and gets no reaction.
+async fn send_direct_messages(
+    http: &Http,
+    outgoing: &Outgoing<'_>,
+    retries: u32,
+) -> Result<(), GoodMorningError> {
+    let direct = Outgoing {
+        members: &[],
+        reaction: None,
+        ..*outgoing
+    };
+
+    for member in outgoing.members {
+        let channel_id = open_dm_channel(http, member, retries).await?;
+        send_to_channel(http, channel_id, None, &direct, retries).await?;
+        info!(member = %member.name, "Direct message delivered");
+    }
+
+    Ok(())
+}
+
+async fn open_dm_channel(
+    http: &Http,
+    member: &Member,
+    retries: u32,
+) -> Result<ChannelId, GoodMorningError> {
+    let user_id = NonZeroU64::new(member.id)
+        .map(UserId::from)
+        .ok_or_else(|| {
+            GoodMorningError::Discord(format!("Member {} has no Discord user id", member.name))
+        })?;
+
+    match with_discord_retries(retries, || user_id.create_dm_channel(http)).await? {
+        Ok(channel) => Ok(channel.id),
+        Err(serenity::Error::Http(e)) if e.status_code() == Some(StatusCode::FORBIDDEN) => {
+            Err(GoodMorningError::Discord(format!(
+                "Discord refused to open a DM with {} (403 Forbidden): DM delivery requires a bot token, \
+                 the bot must share a server with the member, and the member must allow direct messages",
+                member.name
+            )))
+        }
+        Err(e) => Err(GoodMorningError::Discord(format!(
+            "Failed to open DM channel: {}",
+            e
+        ))),
+    }
+}
+
+async fn open_daily_thread(
+    http: &Http,
+    channel_id: ChannelId,
+    retries: u32,
+) -> Result<ChannelId, GoodMorningError> {
+    let name = Local::now().date_naive().format("%Y-%m-%d").to_string();
+
+    if let Some(thread_id) = find_daily_thread(http, channel_id, &name, retries).await? {
+        info!(channel_id = %channel_id, thread_id = %thread_id, "Reusing daily thread '{}'", name);
+        return Ok(thread_id);
+    }
+
+    let builder = CreateThread::new(name.as_str())
+        .kind(ChannelType::PublicThread)
+        .auto_archive_duration(AutoArchiveDuration::OneDay);
+
+    let thread = with_discord_retries(retries, || channel_id.create_thread(http, builder.clone()))
+        .await?
+        .map_err(|e| {
+            GoodMorningError::Discord(format!(
+                "Failed to create thread '{}' in channel {}: {}",
+                name, channel_id, e
+            ))
+        })?;
+
+    info!(channel_id = %channel_id, thread_id = %thread.id, "Created daily thread '{}'", name);
+
+    Ok(thread.id)
+}
+
+/// Looks for an active thread named `name` under `channel_id`, so later slots
+/// of the same day post into the thread the first slot created.
+async fn find_daily_thread(
+    http: &Http,
+    channel_id: ChannelId,
+    name: &str,
+    retries: u32,
+) -> Result<Option<ChannelId>, GoodMorningError> {
+    let lookup_failed = |e: serenity::Error| {
+        GoodMorningError::Discord(format!(
+            "Failed to look up thread '{}' in channel {}: {}",
+            name, channel_id, e
+        ))
+    };
+
+    let channel = with_discord_retries(retries, || channel_id.to_channel(http))
+        .await?
+        .map_err(lookup_failed)?;
+    let Some(guild_id) = channel.guild().map(|channel| channel.guild_id) else {
+        return Ok(None);
+    };
+
+    let active = with_discord_retries(retries, || guild_id.get_active_threads(http))
+        .await?
+        .map_err(lookup_failed)?;
+
+    Ok(active
+        .threads
+        .into_iter()
+        .find(|thread| thread.parent_id == Some(channel_id) && thread.name == name)
+        .map(|thread| thread.id))
+}
+
+async fn send_to_webhook(
+    http: &Http,
+    webhook: &Webhook,
+    outgoing: &Outgoing<'_>,
+    retries: u32,
+) -> Result<(), GoodMorningError> {
+    let allowed_mentions = allowed_mentions(outgoing.members);
+    let create_embed = create_embed(outgoing);
+    let create_attachment = create_attachment(outgoing);
+
+    let chunks = message_chunks(outgoing.content);
+    let last = chunks.len() - 1;
+
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let mut builder = ExecuteWebhook::new()
+            .content(chunk)
+            .allowed_mentions(allowed_mentions.clone());
+
+        if let Some(username) = &webhook.username {
+            builder = builder.username(username);
+        }
+
+        if let Some(avatar_url) = &webhook.avatar_url {
+            builder = builder.avatar_url(avatar_url);
+        }
+
+        if index == last {
+            if let Some(create_embed) = &create_embed {
+                builder = builder.embed(create_embed.clone());
+            }
+
+            if let Some(create_attachment) = &create_attachment {
+                builder = builder.add_file(create_attachment.clone());
+            }
+        }
+
+        with_discord_retries(retries, || {
+            builder
+                .clone()
+                .execute(http, (webhook.id, &webhook.token, false))
+        })
+        .await?
+        .map_err(|e| GoodMorningError::Discord(format!("Failed to send webhook message: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+fn allowed_mentions(members: &[Member]) -> CreateAllowedMentions {
+    let ids = |kind: MentionKind| {
+        members
+            .iter()
+            .filter(move |member| member.kind == kind)
+            .filter_map(|member| NonZeroU64::new(member.id))
+    };
+
+    CreateAllowedMentions::new()
+        .users(ids(MentionKind::User).map(UserId::from))
+        .roles(ids(MentionKind::Role).map(RoleId::from))
+}
+
+fn create_embed(outgoing: &Outgoing<'_>) -> Option<CreateEmbed> {
+    outgoing.embed.map(|embed| {
+        let create_embed = CreateEmbed::new()
+            .description(&embed.description)
+            .colour(embed.color)
+            .field(&embed.weather_title, &embed.weather, false);
+
+        match outgoing.icon {
+            Some(icon) => create_embed.attachment(&icon.filename),
+            None => create_embed,
+        }
+    })
+}
+
+fn create_attachment(outgoing: &Outgoing<'_>) -> Option<CreateAttachment> {
+    outgoing
+        .icon
+        .map(|icon| CreateAttachment::bytes(icon.data.clone(), icon.filename.clone()))
+}
+
+fn message_chunks(message: &str) -> Vec<String> {
+    let chunks = split_message(message, DISCORD_MESSAGE_LIMIT);
+
+    if chunks.is_empty() {
+        vec![String::new()]
+    } else {
+        chunks
+    }
+}
+
+fn split_message(message: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in message.lines() {
+        let current_len = current.chars().count();
+        let line_len = line.chars().count();
+
+        if !current.is_empty() && current_len + 1 + line_len <= limit {
+            current.push('\n');
+            current.push_str(line);
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        if line_len <= limit {
+            current = line.to_string();
+            continue;
+        }
+
+        for word in line.split(' ') {
+            let pieces = word.chars().collect::<Vec<_>>();
+
+            for piece in pieces
+                .chunks(limit)
+                .map(|piece| piece.iter().collect::<String>())
+            {
+                if current.is_empty() {
+                    current = piece;
+                } else if current.chars().count() + 1 + piece.chars().count() <= limit {
+                    current.push(' ');
+                    current.push_str(&piece);
+                } else {
+                    chunks.push(std::mem::replace(&mut current, piece));
+                }
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    struct CannedWeather;
+
+    #[async_trait(?Send)]
+    impl WeatherProvider for CannedWeather {
+        fn name(&self) -> &'static str {
+            "canned"
+        }
+
+        async fn fetch(&self) -> Result<Weather, GoodMorningError> {
+            Ok(Weather {
+                summary: "20°C, clear sky".to_string(),
+                code: Some(0),
+                temperature_c: Some(20.0),
+                apparent_temperature_c: None,
+                precipitation_probability: None,
+                high_uv_index: None,
+            })
+        }
+    }
+
+    struct FixedGreeting;
+
+    #[async_trait(?Send)]
+    impl LlmBackend for FixedGreeting {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        async fn generate(&self, prompt: &str) -> Result<String, GoodMorningError> {
+            assert!(prompt.contains("20°C, clear sky"), "{}", prompt);
+            assert!(prompt.contains("wear a t-shirt"), "{}", prompt);
+            Ok("  Good morning, sunshine!  ".to_string())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: RefCell<Vec<String>>,
+    }
+
+    #[async_trait(?Send)]
+    impl MessageSink for RecordingSink {
+        async fn send(
+            &self,
+            _members: &[Member],
+            message: &str,
+            _embed: Option<&Embed>,
+            _icon: Option<&Attachment>,
+        ) -> Result<(), GoodMorningError> {
+            self.sent.borrow_mut().push(message.to_string());
+            Ok(())
+        }
+    }
+
+    fn test_config(members: &str, cache_dir: PathBuf) -> Config {
+        Config {
+            http: reqwest::Client::new(),
+            delivery: None,
+            members: parse_members_str(members, ',').unwrap(),
+            language: Language::Russian,
+            weather_language: Language::English,
+            city: None,
+            latitude: DEFAULT_LATITUDE,
+            longitude: DEFAULT_LONGITUDE,
+            weather_timezone: DEFAULT_WEATHER_TIMEZONE.to_string(),
+            temperature_unit: TemperatureUnit::Celsius,
+            weather_sources: Vec::new(),
+            weather_retries: 0,
+            cache_dir,
+            db_path: None,
+            send_log: None,
+            weather_ttl: Duration::ZERO,
+            uv_threshold: DEFAULT_UV_THRESHOLD,
+            include_aqi: false,
+            recent_weather: Mutex::default(),
+            ollama_bin: PathBuf::from(DEFAULT_OLLAMA_BIN),
+            ollama_log: None,
+            ollama_url: Url::parse(DEFAULT_OLLAMA_HOST).unwrap(),
+            ollama_model: DEFAULT_OLLAMA_MODEL.to_string(),
+            ollama_ready_timeout: Duration::ZERO,
+            ollama_max_restarts: 0,
+            auto_pull: false,
+            llm_backend: LlmBackendKind::Template,
+            prompt: DEFAULT_PROMPT.to_string(),
+            weekend_prompt: None,
+            system_prompt: None,
+            generation_options: None,
+            llm_retries: 0,
+            llm_timeout: Duration::from_secs(DEFAULT_LLM_TIMEOUT_SECS),
+            stream: false,
+            fallback_template: None,
+            use_embed: false,
+            per_member: false,
+            mention_order: MentionOrder::Config,
+            mention_seed: None,
+            footer: None,
+            schedule: None,
+            jitter: Duration::ZERO,
+            jitter_seed: None,
+            weekdays_only: false,
+            health_addr: None,
+            dry_run: false,
+            force: false,
+            max_greeting_chars: DEFAULT_MAX_GREETING_CHARS,
+            attach_icon: false,
+            reaction: None,
+            discord_retries: 0,
+            debug_prompt: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn greeting_pipeline_sends_formatted_message_with_mentions() {
+        let cache_dir = env::temp_dir().join(format!("good_morning_pipeline_{}", process::id()));
+        let mut config = test_config("Anna,123,Moderators,@&456", cache_dir.clone());
+        config.delivery = Some(Delivery::Webhook(Webhook {
+            id: WebhookId::new(1),
+            token: "token".to_string(),
+            username: None,
+            avatar_url: None,
+        }));
+
+        let providers: Vec<Box<dyn WeatherProvider>> = vec![Box::new(CannedWeather)];
+        let sink = RecordingSink::default();
+
+        let sent = run_slot(&config, None, &providers, Some(&FixedGreeting), &sink)
+            .await
+            .unwrap();
+
+        assert!(sent);
+        assert_eq!(
+            sink.sent.borrow().as_slice(),
+            ["☀️ Good morning, sunshine!\n<@123> <@&456>"]
+        );
+
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[tokio::test]
+    async fn http_client_sends_the_good_morning_user_agent() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..len]).to_lowercase()
+        });
+
+        let client = http_client(Duration::from_secs(5)).unwrap();
+        client.get(url).send().await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.contains(&format!("user-agent: {}\r\n", USER_AGENT)));
+    }
+
+    #[test]
+    fn weather_codes_map_to_wmo_descriptions() {
+        assert_eq!(map_weather_code_to_description(0), "clear sky");
+        assert_eq!(map_weather_code_to_description(1), "mainly clear");
+        assert_eq!(map_weather_code_to_description(3), "overcast");
+        assert_eq!(
+            map_weather_code_to_description(57),
+            "dense freezing drizzle"
+        );
+        assert_eq!(map_weather_code_to_description(66), "light freezing rain");
+        assert_eq!(map_weather_code_to_description(75), "heavy snowfall");
+        assert_eq!(map_weather_code_to_description(77), "snow grains");
+        assert_eq!(
+            map_weather_code_to_description(99),
+            "thunderstorm with heavy hail"
+        );
+        assert_eq!(map_weather_code_to_description(42), "unknown weather");
+    }
+
+    #[test]
+    fn weather_descriptions_follow_language() {
+        assert_eq!(Language::Russian.weather_description(0), "ясно");
+        assert_eq!(Language::Russian.weather_description(63), "умеренный дождь");
+        assert_eq!(Language::Russian.weather_description(73), "умеренный снег");
+        assert_eq!(Language::English.weather_description(0), "clear sky");
+        assert_eq!(Language::Japanese.weather_description(0), "clear sky");
+    }
+
+    #[test]
+    fn wttr_summary_follows_language() {
+        let current: WttrCondition = serde_json::from_value(serde_json::json!({
+            "temp_C": "12",
+            "temp_F": "54",
+            "weatherDesc": [{ "value": "Light rain" }],
+            "lang_ru": [{ "value": "Небольшой дождь" }],
+            "windspeedKmph": "9",
+            "humidity": "81"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            wttr_summary(&current, TemperatureUnit::Celsius, Language::Russian),
+            "12°C, небольшой дождь, ветер 9 km/h, влажность 81%"
+        );
+        assert_eq!(
+            wttr_summary(&current, TemperatureUnit::Fahrenheit, Language::English),
+            "54°F, light rain, wind 9 km/h, humidity 81%"
+        );
+        assert_eq!(
+            wttr_summary(&current, TemperatureUnit::Celsius, Language::Japanese),
+            "12°C, light rain, wind 9 km/h, humidity 81%"
+        );
+    }
+
+    #[test]
+    fn weather_labels_follow_language() {
+        assert_eq!(
+            Language::Russian.wind("5 km/h", Some(315.0), Some("9 km/h")),
+            "ветер 5 km/h, направление СЗ, порывы 9 km/h"
+        );
+        assert_eq!(
+            Language::English.wind("5 km/h", Some(315.0), Some("9 km/h")),
+            "wind 5 km/h from NW, gusts 9 km/h"
+        );
+        assert_eq!(
+            Language::Russian.high_and_low("12°C", "4°C"),
+            "максимум 12°C, минимум 4°C"
+        );
+        assert_eq!(
+            Language::Russian.chance_of_rain(40.0),
+            "вероятность дождя 40%"
+        );
+        assert_eq!(Language::English.chance_of_rain(40.0), "40% chance of rain");
+        assert_eq!(
+            Language::Russian.air_quality(25.0),
+            "качество воздуха 25 (удовлетворительное)"
+        );
+        assert_eq!(Language::Russian.sunrise("07:12"), "восход 07:12");
+    }
+
+    #[test]
+    fn template_greeting_describes_weather_in_russian() {
+        let members = parse_members_str("Анна,123,Борис,456,Вера,789", ',').unwrap();
+        let weather = Weather {
+            summary: "3°C, moderate rain".to_string(),
+            code: Some(63),
+            temperature_c: Some(3.4),
+            apparent_temperature_c: Some(-1.2),
+            precipitation_probability: Some(80.0),
+            high_uv_index: None,
+        };
+
+        assert_eq!(
+            template_greeting(&members, &weather),
+            "Доброе утро, Анна, Борис и Вера! Сегодня умеренный дождь, 3°C, ощущается как -1°C, \
+             вероятность дождя 80%. \
+             Наденьте тёплое пальто, шапку и перчатки и не забудьте зонт или дождевик. \
+             Хорошего вам дня!"
+        );
+    }
+
+    #[test]
+    fn template_greeting_falls_back_to_summary() {
+        let weather = Weather {
+            summary: "облачно".to_string(),
+            code: None,
+            temperature_c: None,
+            apparent_temperature_c: None,
+            precipitation_probability: None,
+            high_uv_index: None,
+        };
+
+        assert_eq!(
+            template_greeting(&[], &weather),
+            "Доброе утро, друзья! Погода на сегодня: облачно. Хорошего вам дня!"
+        );
+    }
+
+    #[test]
+    fn send_history_marks_the_day_as_sent() {
+        let path = env::temp_dir().join(format!("good_morning_history_{}.db", process::id()));
+        let _ = fs::remove_file(&path);
+        let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+
+        let morning = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+
+        assert!(!sent_on(&path, &today, None).unwrap());
+
+        record_history(
+            &path,
+            Some(morning),
+            &[SentMessage {
+                channel: "channel 42".to_string(),
+                weather: "5°C, fog",
+                message: "Good morning!",
+                members: &[],
+            }],
+        )
+        .unwrap();
+
+        assert!(sent_on(&path, &today, None).unwrap());
+        assert!(sent_on(&path, &today, Some(morning)).unwrap());
+        assert!(!sent_on(&path, &today, Some(noon)).unwrap());
+        assert!(!sent_on(&path, "2000-01-01", None).unwrap());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn weather_cache_ignores_entries_for_other_settings() {
+        let cache_dir = env::temp_dir().join(format!("good_morning_cache_{}", process::id()));
+        let weather = Weather {
+            summary: "5°C, fog".to_string(),
+            code: Some(45),
+            temperature_c: Some(5.0),
+            apparent_temperature_c: None,
+            precipitation_probability: None,
+            high_uv_index: None,
+        };
+
+        write_weather_cache(&cache_dir, "Moscow|55.7558,37.6173|celsius|en", &weather).unwrap();
+
+        assert_eq!(
+            read_weather_cache(&cache_dir, "Moscow|55.7558,37.6173|celsius|en")
+                .map(|weather| weather.summary),
+            Some("5°C, fog".to_string())
+        );
+        assert!(read_weather_cache(&cache_dir, "Tokyo|55.7558,37.6173|celsius|en").is_none());
+        assert!(read_weather_cache(&cache_dir, "Moscow|55.7558,37.6173|fahrenheit|en").is_none());
+        assert!(read_weather_cache(&cache_dir, "Moscow|55.7558,37.6173|celsius|ru").is_none());
+
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn mentions_follow_the_configured_order() {
+        let members = parse_members_str("carol,3,Alice,1,bob,2", ',').unwrap();
+        let names = |members: &[Member]| {
+            members
+                .iter()
+                .map(|member| member.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&order_mentions(&members, MentionOrder::Config, None)),
+            ["carol", "Alice", "bob"]
+        );
+        assert_eq!(
+            names(&order_mentions(&members, MentionOrder::Alphabetical, None)),
+            ["Alice", "bob", "carol"]
+        );
+
+        let shuffled = names(&order_mentions(&members, MentionOrder::Shuffle, Some(7)));
+        assert_eq!(
+            shuffled,
+            names(&order_mentions(&members, MentionOrder::Shuffle, Some(7)))
+        );
+
+        let mut sorted = shuffled;
+        sorted.sort();
+        assert_eq!(sorted, ["Alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn clean_greeting_strips_model_artifacts() {
+        assert_eq!(
+            clean_greeting("\"Good morning, Anna! It's sunny today.\""),
+            "Good morning, Anna! It's sunny today."
+        );
+        assert_eq!(
+            clean_greeting("Here's your greeting:\n\n«Доброе утро!»"),
+            "Доброе утро!"
+        );
+        assert_eq!(
+            clean_greeting("Sure! Here is a cute morning greeting:\nGood morning uwu"),
+            "Good morning uwu"
+        );
+        assert_eq!(
+            clean_greeting("```text\nGood morning!\n```"),
+            "Good morning!"
+        );
+        assert_eq!(
+            clean_greeting("Good morning!\n\n\n\nStay warm!  \n"),
+            "Good morning!\n\nStay warm!"
+        );
+    }
+
+    #[test]
+    fn clean_greeting_keeps_genuine_content() {
+        assert_eq!(
+            clean_greeting("\"Rise and shine\", said the sun. \"Good morning!\""),
+            "\"Rise and shine\", said the sun. \"Good morning!\""
+        );
+        assert_eq!(
+            clean_greeting("Here comes the sun:\n"),
+            "Here comes the sun:"
+        );
+        assert_eq!(
+            clean_greeting("Good morning, team:\nIt's 5°C outside."),
+            "Good morning, team:\nIt's 5°C outside."
+        );
+    }
+
+    #[test]
+    fn footer_goes_after_or_before_mentions() {
+        let members = parse_members_str("Anna,123", ',').unwrap();
+        let mut footer = Footer {
+            text: "— Good Morning Bot 🌅".to_string(),
+            before_mentions: false,
+        };
+
+        assert_eq!(format_message(&members, "Hi!", None), "Hi!\n<@123>");
+        assert_eq!(
+            format_message(&members, "Hi!", Some(&footer)),
+            "Hi!\n<@123>\n— Good Morning Bot 🌅"
+        );
+
+        footer.before_mentions = true;
+        assert_eq!(
+            format_message(&members, "Hi!", Some(&footer)),
+            "Hi!\n— Good Morning Bot 🌅\n<@123>"
+        );
+        assert_eq!(
+            format_message(&[], "Hi!", Some(&footer)),
+            "Hi!\n— Good Morning Bot 🌅"
+        );
+
+        footer.text = "Sent on {date}".to_string();
+        assert_eq!(
+            format_message(&[], "Hi!", Some(&footer)),
+            format!(
+                "Hi!\nSent on {}",
+                Local::now().date_naive().format("%Y-%m-%d")
+            )
+        );
+    }
+
+    #[test]
+    fn wind_direction_maps_to_compass_points() {
+        assert_eq!(compass_direction(0.0), "N");
+        assert_eq!(compass_direction(22.0), "N");
+        assert_eq!(compass_direction(23.0), "NE");
+        assert_eq!(compass_direction(180.0), "S");
+        assert_eq!(compass_direction(315.0), "NW");
+        assert_eq!(compass_direction(350.0), "N");
+        assert_eq!(compass_direction(360.0), "N");
+    }
+
+    #[test]
+    fn hourly_index_picks_the_nearest_hour() {
+        let hourly = HourlyWeather {
+            time: ["2024-05-01T07:00", "2024-05-01T08:00", "2024-05-01T09:00"]
+                .map(str::to_string)
+                .to_vec(),
+            relative_humidity_2m: Vec::new(),
+            apparent_temperature: Vec::new(),
+            wind_gusts_10m: Vec::new(),
+            cloud_cover: Vec::new(),
+        };
+
+        assert_eq!(hourly.current_hour_index("2024-05-01T08:00"), Some(1));
+        assert_eq!(hourly.current_hour_index("2024-05-01T08:15"), Some(1));
+        assert_eq!(hourly.current_hour_index("2024-05-01T08:45"), Some(2));
+        assert_eq!(hourly.current_hour_index("2024-05-01T23:00"), Some(2));
+        assert_eq!(hourly.current_hour_index("not a time"), None);
+    }
+
+    #[test]
+    fn jitter_is_deterministic_and_within_the_window() {
+        let window = Duration::from_secs(300);
+        let offsets = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..5)
+                .map(|_| jitter_offset(&mut rng, window))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(offsets(42), offsets(42));
+        assert_ne!(offsets(42), offsets(43));
+        assert!(offsets(42).iter().all(|offset| *offset <= window));
+        assert_eq!(
+            jitter_offset(&mut StdRng::seed_from_u64(42), Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn rate_limit_waits_double_until_the_cap() {
+        assert_eq!(
+            rate_limit_delay(0, Duration::ZERO).unwrap(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            rate_limit_delay(3, Duration::from_secs(7)).unwrap(),
+            Duration::from_secs(8)
+        );
+
+        assert!(matches!(
+            rate_limit_delay(5, Duration::from_secs(31)),
+            Err(GoodMorningError::Discord(message)) if message == "rate limited, retry after 32s"
+        ));
+    }
+
+    #[test]
+    fn raw_mentions_are_detected_in_generated_text() {
+        assert_eq!(find_raw_mention("Good morning <@123>!"), Some("<@123>"));
+        assert_eq!(find_raw_mention("Hi <@!42> and <@&7>"), Some("<@!42>"));
+        assert_eq!(
+            find_raw_mention("Hello <@&987654321>"),
+            Some("<@&987654321>")
+        );
+        assert_eq!(find_raw_mention("Good morning, Anna!"), None);
+        assert_eq!(find_raw_mention("email me <@ home> or <@abc>"), None);
+        assert_eq!(find_raw_mention("unterminated <@123"), None);
+    }
+
+    #[test]
+    fn sanitize_greeting_strips_nested_mass_pings() {
+        assert_eq!(
+            sanitize_greeting("Hi @everyone and @here!", 100),
+            "Hi  and !"
+        );
+        assert_eq!(sanitize_greeting("@@everyoneeveryone", 100), "");
+        assert_eq!(sanitize_greeting("@ever@hereyone", 100), "");
+        assert_eq!(sanitize_greeting("@he@everyonere wake up", 100), "wake up");
+    }
+
+    #[test]
+    fn clothing_follows_temperature_and_precipitation() {
+        assert_eq!(
+            clothing_suggestion(-15.0, 0, None, None),
+            "wear a heavy winter coat, a hat, a scarf and warm gloves"
+        );
+        assert_eq!(
+            clothing_suggestion(3.0, 73, Some(90.0), None),
+            "wear a warm coat, a hat and gloves, and bring waterproof boots"
+        );
+        assert_eq!(
+            clothing_suggestion(15.0, 63, None, Some(7.0)),
+            "wear a light jacket or a sweater, and bring an umbrella or a raincoat"
+        );
+        assert_eq!(
+            clothing_suggestion(28.0, 0, Some(10.0), None),
+            "wear light breathable clothing, and bring sunglasses and sunscreen"
+        );
+        assert_eq!(
+            clothing_suggestion(20.0, 2, Some(70.0), None),
+            "wear a t-shirt with something light for the evening, and bring an umbrella or a raincoat"
+        );
+        assert_eq!(
+            clothing_suggestion(20.0, 2, Some(10.0), Some(8.0)),
+            "wear a t-shirt with something light for the evening, and bring sunglasses and sunscreen"
+        );
+    }
+
+    fn names_and_ids(members: &[Member]) -> Vec<(&str, u64)> {
+        members
+            .iter()
+            .map(|member| (member.name.as_str(), member.id))
+            .collect()
+    }
+
+    #[test]
+    fn parse_members_reads_well_formed_pairs() {
+        let members = parse_members_str("Anna,123,Bob,@&456", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Anna", 123), ("Bob", 456)]);
+        assert_eq!(members[0].kind, MentionKind::User);
+        assert_eq!(members[1].kind, MentionKind::Role);
+    }
+
+    #[test]
+    fn parse_members_rejects_odd_field_count() {
+        let error = parse_members_str("Anna,123,Bob", ',').unwrap_err();
+
+        assert!(error.contains("entry 2 ('Bob'): missing id"), "{}", error);
+    }
+
+    #[test]
+    fn parse_members_rejects_non_numeric_id() {
+        let error = parse_members_str("Anna,abc,Bob,456", ',').unwrap_err();
+
+        assert!(
+            error.contains("entry 1 ('Anna,abc'): invalid id"),
+            "{}",
+            error
+        );
+        assert!(!error.contains("Bob"), "{}", error);
+    }
+
+    #[test]
+    fn parse_members_accepts_empty_input() {
+        assert!(parse_members_str("", ',').unwrap().is_empty());
+        assert!(parse_members_str("   ", ',').unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_members_strips_unquoted_leading_and_trailing_whitespace() {
+        let members = parse_members_str("  Anna , 123 ,\tBob,456\n", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Anna", 123), ("Bob", 456)]);
+    }
+
+    #[test]
+    fn parse_members_honours_custom_delimiter() {
+        let members = parse_members_str("Anna, the Great;123;Bob;456", ';').unwrap();
+
+        assert_eq!(
+            names_and_ids(&members),
+            [("Anna, the Great", 123), ("Bob", 456)]
+        );
+    }
+
+    #[test]
+    fn parse_members_accepts_quoted_names_with_commas() {
+        let members = parse_members_str("\"Anna, the Great\",123,Bob,\"456\"", ',').unwrap();
+
+        assert_eq!(
+            names_and_ids(&members),
+            [("Anna, the Great", 123), ("Bob", 456)]
+        );
+    }
+
+    #[test]
+    fn parse_members_unescapes_doubled_quotes() {
+        let members = parse_members_str("\"Anna \"\"Boss\"\", Jr\",123", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Anna \"Boss\", Jr", 123)]);
+    }
+
+    #[test]
+    fn parse_members_rejects_unterminated_quote() {
+        let error = parse_members_str("\"Anna,123", ',').unwrap_err();
+
+        assert!(error.contains("unterminated quote"), "{}", error);
+    }
+
+    #[test]
+    fn parse_members_keeps_spaces_inside_names() {
+        let members = parse_members_str("Anna Maria,123,Bob,456", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Anna Maria", 123), ("Bob", 456)]);
+    }
+
+    #[test]
+    fn parse_members_trims_spaces_around_fields() {
+        let members = parse_members_str("Alice , 123, Bob ,  @& 456  ", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Alice", 123), ("Bob", 456)]);
+        assert_eq!(members[1].kind, MentionKind::Role);
+    }
+
+    #[test]
+    fn parse_members_trims_tabs_around_fields() {
+        let members = parse_members_str("\tAlice\t,\t123\t", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Alice", 123)]);
+    }
+
+    #[test]
+    fn parse_members_trims_newlines_around_fields() {
+        let members = parse_members_str("Alice,\n123,\r\nBob\n,456\r\n", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [("Alice", 123), ("Bob", 456)]);
+    }
+
+    #[test]
+    fn parse_members_keeps_spaces_inside_quotes() {
+        let members = parse_members_str(" \" Alice \" , \" 123 \" ", ',').unwrap();
+
+        assert_eq!(names_and_ids(&members), [(" Alice ", 123)]);
+    }
+}